@@ -0,0 +1,580 @@
+use iced::futures::{self, channel::mpsc, channel::oneshot, FutureExt, StreamExt};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+
+const AGENT_PATH: &str = "/org/wawi/BluetoothAgent";
+/// How long a request waits for an answer before it's rejected, a bit less
+/// than BlueZ waits for us.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(25);
+
+#[zbus::proxy(interface = "org.bluez.Adapter1", default_service = "org.bluez")]
+trait Adapter {
+    #[zbus(property)]
+    fn powered(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn set_powered(&self, value: bool) -> zbus::Result<()>;
+
+    fn start_discovery(&self) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(interface = "org.bluez.Device1", default_service = "org.bluez")]
+trait Device {
+    fn connect(&self) -> zbus::Result<()>;
+
+    fn disconnect(&self) -> zbus::Result<()>;
+
+    /// Doesn't return until the agent has been through with the user.
+    fn pair(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn alias(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_trusted(&self, value: bool) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.bluez.AgentManager1",
+    default_service = "org.bluez",
+    default_path = "/org/bluez"
+)]
+trait AgentManager {
+    fn register_agent(&self, agent: &ObjectPath<'_>, capability: &str) -> zbus::Result<()>;
+
+    fn request_default_agent(&self, agent: &ObjectPath<'_>) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct BluetoothDevice {
+    pub path: OwnedObjectPath,
+    pub name: String,
+    /// Freedesktop icon name, e.g. `audio-headset`.
+    pub icon: Option<String>,
+    pub paired: bool,
+    pub connected: bool,
+    /// Percentage, only reported by some connected devices.
+    pub battery: Option<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub enum BluetoothInfo {
+    NotAvailable,
+    Available {
+        powered: bool,
+        discovering: bool,
+        /// Paired devices, connected ones first.
+        devices: Vec<BluetoothDevice>,
+        /// Unpaired devices in range, found through discovery.
+        discovered: Vec<BluetoothDevice>,
+    },
+}
+
+type Properties = HashMap<String, OwnedValue>;
+
+fn string_property(properties: &Properties, key: &str) -> Option<String> {
+    properties.get(key)?.downcast_ref::<String>().ok()
+}
+
+fn bool_property(properties: &Properties, key: &str) -> bool {
+    properties
+        .get(key)
+        .and_then(|value| value.downcast_ref::<bool>().ok())
+        .unwrap_or_default()
+}
+
+fn bluetooth_device(
+    path: OwnedObjectPath,
+    device: &Properties,
+    battery: Option<&Properties>,
+) -> BluetoothDevice {
+    let name = string_property(device, "Alias")
+        .or_else(|| string_property(device, "Name"))
+        .or_else(|| string_property(device, "Address"))
+        .unwrap_or_default();
+
+    BluetoothDevice {
+        path,
+        name,
+        icon: string_property(device, "Icon"),
+        paired: bool_property(device, "Paired"),
+        connected: bool_property(device, "Connected"),
+        battery: battery
+            .and_then(|battery| battery.get("Percentage"))
+            .and_then(|percentage| percentage.downcast_ref::<u8>().ok()),
+    }
+}
+
+async fn object_manager(
+    conn: &zbus::Connection,
+) -> zbus::Result<zbus::fdo::ObjectManagerProxy<'static>> {
+    zbus::fdo::ObjectManagerProxy::builder(conn)
+        .destination("org.bluez")?
+        .path("/")?
+        .build()
+        .await
+}
+
+/// Only the first adapter is looked at, which is all most machines have.
+async fn bluetooth_info(conn: &zbus::Connection) -> zbus::Result<BluetoothInfo> {
+    let objects = object_manager(conn).await?.get_managed_objects().await?;
+
+    let adapter = objects
+        .iter()
+        .filter_map(|(path, interfaces)| Some((path, interfaces.get(ADAPTER_INTERFACE)?)))
+        .min_by_key(|(path, _)| path.as_str());
+    let Some((adapter_path, adapter)) = adapter else {
+        return Ok(BluetoothInfo::NotAvailable);
+    };
+
+    let adapter_prefix = format!("{}/", adapter_path.as_str());
+    let mut devices = Vec::new();
+    let mut discovered = Vec::new();
+
+    for (path, interfaces) in &objects {
+        if !path.as_str().starts_with(&adapter_prefix) {
+            continue;
+        }
+        let Some(device) = interfaces.get(DEVICE_INTERFACE) else {
+            continue;
+        };
+
+        let bluetooth_device =
+            bluetooth_device(path.clone(), device, interfaces.get(BATTERY_INTERFACE));
+
+        if bluetooth_device.paired {
+            devices.push(bluetooth_device);
+        } else if device.contains_key("RSSI") {
+            // BlueZ remembers devices long gone; a signal strength means it's in range.
+            discovered.push(bluetooth_device);
+        }
+    }
+
+    devices.sort_by(|a, b| b.connected.cmp(&a.connected).then(a.name.cmp(&b.name)));
+    discovered.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(BluetoothInfo::Available {
+        powered: bool_property(adapter, "Powered"),
+        discovering: bool_property(adapter, "Discovering"),
+        devices,
+        discovered,
+    })
+}
+
+async fn adapter_path(conn: &zbus::Connection) -> zbus::Result<Option<OwnedObjectPath>> {
+    let objects = object_manager(conn).await?.get_managed_objects().await?;
+
+    Ok(objects
+        .into_iter()
+        .filter(|(_, interfaces)| interfaces.contains_key(ADAPTER_INTERFACE))
+        .map(|(path, _)| path)
+        .min_by(|a, b| a.as_str().cmp(b.as_str())))
+}
+
+pub async fn set_powered(powered: bool) -> bool {
+    let result = async {
+        let conn = zbus::Connection::system().await?;
+        let Some(path) = adapter_path(&conn).await? else {
+            return Ok(());
+        };
+
+        AdapterProxy::builder(&conn)
+            .path(path)?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?
+            .set_powered(powered)
+            .await
+    }
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Couldn't toggle the Bluetooth: {err}");
+    }
+
+    powered
+}
+
+/// Connects or disconnects every profile the device supports.
+pub async fn set_connected(path: OwnedObjectPath, connected: bool) -> Result<(), String> {
+    let result = async {
+        let conn = zbus::Connection::system().await?;
+        let device = DeviceProxy::builder(&conn)
+            .path(path)?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?;
+
+        if connected {
+            device.connect().await
+        } else {
+            device.disconnect().await
+        }
+    }
+    .await;
+
+    result.map_err(|err| {
+        eprintln!("Couldn't change the Bluetooth connection: {err}");
+        err.to_string()
+    })
+}
+
+/// Pairs with the device, then trusts and connects it so it reconnects on its own.
+pub async fn pair(path: OwnedObjectPath) -> Result<(), String> {
+    let result = async {
+        let conn = zbus::Connection::system().await?;
+        let device = DeviceProxy::builder(&conn)
+            .path(path)?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?;
+
+        device.pair().await?;
+        device.set_trusted(true).await?;
+        device.connect().await
+    }
+    .await;
+
+    result.map_err(|err| {
+        eprintln!("Couldn't pair the Bluetooth device: {err}");
+        err.to_string()
+    })
+}
+
+/// BlueZ stops discovering once the client that started it goes away, so the
+/// connection lives as long as the stream.
+async fn discovery_stream() -> zbus::Result<impl futures::Stream<Item = Result<(), String>>> {
+    let conn = zbus::Connection::system().await?;
+    let Some(path) = adapter_path(&conn).await? else {
+        return Err(zbus::Error::Failure("No Bluetooth adapter".to_owned()));
+    };
+
+    AdapterProxy::builder(&conn)
+        .path(path)?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?
+        .start_discovery()
+        .await?;
+
+    let keep_alive = futures::stream::unfold(conn, |conn| async move {
+        futures::future::pending::<()>().await;
+        Some((Ok(()), conn))
+    });
+
+    Ok(futures::stream::once(async { Ok(()) }).chain(keep_alive))
+}
+
+/// Discovers devices for as long as the subscription is alive.
+pub fn discovery_subscription<I>(id: I) -> iced::Subscription<Result<(), String>>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("bluez-discovery", id),
+        async move {
+            match discovery_stream().await {
+                Ok(stream) => stream.boxed(),
+                Err(err) => {
+                    eprintln!("Couldn't start Bluetooth discovery: {err}");
+                    futures::stream::once(async move { Err(err.to_string()) })
+                        .chain(futures::stream::pending())
+                        .boxed()
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub enum PairingKind {
+    /// Both sides show the passkey and the user checks they match.
+    Confirm(u32),
+    /// The device wants to pair, or to use a service, without a passkey.
+    Authorize,
+    /// The user types the passkey on the device.
+    DisplayPasskey(u32),
+    DisplayPinCode(String),
+}
+
+/// Answers a pending agent request. Only the first answer counts.
+#[derive(Clone)]
+pub struct PairingReply(Arc<Mutex<Option<oneshot::Sender<bool>>>>);
+
+impl PairingReply {
+    pub fn send(&self, accepted: bool) {
+        if let Some(sender) = self.0.lock().ok().and_then(|mut sender| sender.take()) {
+            let _ = sender.send(accepted);
+        }
+    }
+}
+
+impl std::fmt::Debug for PairingReply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PairingReply").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PairingRequest {
+    pub device: String,
+    pub kind: PairingKind,
+    pub reply: PairingReply,
+}
+
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    Request(PairingRequest),
+    /// BlueZ gave up on the request, e.g. because the device went away, or
+    /// it wasn't answered in time.
+    Cancelled,
+}
+
+#[derive(Debug, zbus::DBusError)]
+#[zbus(prefix = "org.bluez.Error")]
+enum AgentError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    Rejected(String),
+}
+
+struct Agent {
+    events: mpsc::UnboundedSender<AgentEvent>,
+}
+
+impl Agent {
+    async fn device_name(conn: &zbus::Connection, path: &ObjectPath<'_>) -> String {
+        let alias = async {
+            DeviceProxy::builder(conn)
+                .path(path.to_owned())?
+                .cache_properties(zbus::CacheProperties::No)
+                .build()
+                .await?
+                .alias()
+                .await
+        };
+
+        alias.await.unwrap_or_else(|_| path.to_string())
+    }
+
+    fn show(&self, device: String, kind: PairingKind) {
+        let (reply, _) = oneshot::channel();
+        let reply = PairingReply(Arc::new(Mutex::new(Some(reply))));
+
+        let _ = self
+            .events
+            .unbounded_send(AgentEvent::Request(PairingRequest {
+                device,
+                kind,
+                reply,
+            }));
+    }
+
+    /// Waits for the user to answer in the panel, rejecting the request if
+    /// they don't in time.
+    async fn ask(&self, device: String, kind: PairingKind) -> Result<(), AgentError> {
+        let (reply, answer) = oneshot::channel();
+        let reply = PairingReply(Arc::new(Mutex::new(Some(reply))));
+
+        self.events
+            .unbounded_send(AgentEvent::Request(PairingRequest {
+                device,
+                kind,
+                reply,
+            }))
+            .map_err(|_| AgentError::Rejected("The panel is gone".to_owned()))?;
+
+        match tokio::time::timeout(REQUEST_TIMEOUT, answer).await {
+            Ok(Ok(true)) => Ok(()),
+            Ok(Ok(false) | Err(_)) => Err(AgentError::Rejected("Rejected by the user".to_owned())),
+            Err(_) => {
+                let _ = self.events.unbounded_send(AgentEvent::Cancelled);
+                Err(AgentError::Rejected("Nobody answered in time".to_owned()))
+            }
+        }
+    }
+}
+
+/// Capability is `DisplayYesNo`, so BlueZ shouldn't ask us to type anything in.
+#[zbus::interface(name = "org.bluez.Agent1")]
+impl Agent {
+    fn release(&self) {}
+
+    fn request_pin_code(&self, _device: ObjectPath<'_>) -> Result<String, AgentError> {
+        Err(AgentError::Rejected("PIN entry isn't supported".to_owned()))
+    }
+
+    fn request_passkey(&self, _device: ObjectPath<'_>) -> Result<u32, AgentError> {
+        Err(AgentError::Rejected(
+            "Passkey entry isn't supported".to_owned(),
+        ))
+    }
+
+    async fn display_pin_code(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: ObjectPath<'_>,
+        pincode: String,
+    ) {
+        let device = Self::device_name(conn, &device).await;
+        self.show(device, PairingKind::DisplayPinCode(pincode));
+    }
+
+    async fn display_passkey(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: ObjectPath<'_>,
+        passkey: u32,
+        _entered: u16,
+    ) {
+        let device = Self::device_name(conn, &device).await;
+        self.show(device, PairingKind::DisplayPasskey(passkey));
+    }
+
+    async fn request_confirmation(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: ObjectPath<'_>,
+        passkey: u32,
+    ) -> Result<(), AgentError> {
+        let device = Self::device_name(conn, &device).await;
+        self.ask(device, PairingKind::Confirm(passkey)).await
+    }
+
+    async fn request_authorization(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: ObjectPath<'_>,
+    ) -> Result<(), AgentError> {
+        let device = Self::device_name(conn, &device).await;
+        self.ask(device, PairingKind::Authorize).await
+    }
+
+    async fn authorize_service(
+        &self,
+        #[zbus(connection)] conn: &zbus::Connection,
+        device: ObjectPath<'_>,
+        _uuid: String,
+    ) -> Result<(), AgentError> {
+        let device = Self::device_name(conn, &device).await;
+        self.ask(device, PairingKind::Authorize).await
+    }
+
+    fn cancel(&self) {
+        let _ = self.events.unbounded_send(AgentEvent::Cancelled);
+    }
+}
+
+/// Serves a pairing agent on `conn` and makes it the default one. Requests
+/// keep coming for as long as the stream, which holds on to `conn`, is alive;
+/// BlueZ forgets the agent once the connection is closed.
+pub async fn agent_stream(
+    conn: zbus::Connection,
+) -> zbus::Result<impl futures::Stream<Item = AgentEvent>> {
+    let (events, receiver) = mpsc::unbounded();
+    conn.object_server()
+        .at(AGENT_PATH, Agent { events })
+        .await?;
+
+    let path = ObjectPath::try_from(AGENT_PATH)?;
+    let agent_manager = AgentManagerProxy::new(&conn).await?;
+    agent_manager.register_agent(&path, "DisplayYesNo").await?;
+    agent_manager.request_default_agent(&path).await?;
+
+    Ok(futures::stream::unfold(
+        (conn, receiver),
+        |(conn, mut receiver)| async move {
+            let event = receiver.next().await?;
+            Some((event, (conn, receiver)))
+        },
+    ))
+}
+
+pub fn agent_subscription<I>(id: I) -> iced::Subscription<AgentEvent>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("bluez-agent", id),
+        async move {
+            let stream = match zbus::Connection::system().await {
+                Ok(conn) => agent_stream(conn).await,
+                Err(err) => Err(err),
+            };
+
+            match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("Couldn't register the Bluetooth agent: {err}");
+                    futures::future::pending().await
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+/// Follows BlueZ on `conn`, with signals read from `signal_conn` so a
+/// backed up queue can't stall the snapshots. Both are parameters so a
+/// private bus can stand in for the system one.
+pub async fn event_stream(
+    conn: zbus::Connection,
+    signal_conn: zbus::Connection,
+) -> zbus::Result<impl futures::Stream<Item = BluetoothInfo>> {
+    // Property changes as well as adapters and devices coming and going.
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.bluez")?
+        .build();
+    let signals = zbus::MessageStream::for_match_rule(rule, &signal_conn, None).await?;
+
+    let initial = bluetooth_info(&conn).await?;
+
+    // A failed snapshot keeps the last state, so a passing D-Bus error
+    // doesn't hide the devices, or a pairing going on.
+    let updates = signals.ready_chunks(64).filter_map(move |_| {
+        let conn = conn.clone();
+        async move {
+            bluetooth_info(&conn)
+                .await
+                .inspect_err(|err| eprintln!("Couldn't read the Bluetooth state: {err}"))
+                .ok()
+        }
+    });
+
+    Ok(futures::stream::once(async { initial }).chain(updates))
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<BluetoothInfo>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("bluez", id),
+        async move {
+            let stream = async {
+                let conn = zbus::Connection::system().await?;
+                let signal_conn = zbus::Connection::system().await?;
+                event_stream(conn, signal_conn).await
+            };
+
+            match stream.await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("An error has ocurred: {err}");
+                    futures::future::pending().await
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
@@ -0,0 +1,416 @@
+use std::{
+    fs,
+    io::{self, Read, Write},
+    os::fd::AsRawFd,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+/// Raw access to an I2C bus. Implemented over `/dev/i2c-*` by [`I2cDevice`],
+/// but anything that behaves like a DDC/CI monitor will do.
+pub trait I2cTransport {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()>;
+
+    fn read(&mut self, address: u16, buffer: &mut [u8]) -> io::Result<()>;
+}
+
+pub struct I2cDevice {
+    file: fs::File,
+    address: Option<u16>,
+}
+
+impl I2cDevice {
+    /// `ioctl` request to select the slave address, from `linux/i2c-dev.h`.
+    const I2C_SLAVE: libc::c_ulong = 0x0703;
+
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let file = fs::OpenOptions::new().read(true).write(true).open(path)?;
+
+        Ok(Self {
+            file,
+            address: None,
+        })
+    }
+
+    fn select(&mut self, address: u16) -> io::Result<()> {
+        if self.address == Some(address) {
+            return Ok(());
+        }
+
+        // SAFETY: I2C_SLAVE takes the address by value and the descriptor is owned by `self`.
+        let result = unsafe {
+            libc::ioctl(
+                self.file.as_raw_fd(),
+                Self::I2C_SLAVE,
+                address as libc::c_ulong,
+            )
+        };
+        if result < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        self.address = Some(address);
+        Ok(())
+    }
+}
+
+impl I2cTransport for I2cDevice {
+    fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+        self.select(address)?;
+        self.file.write_all(data)
+    }
+
+    fn read(&mut self, address: u16, buffer: &mut [u8]) -> io::Result<()> {
+        self.select(address)?;
+        self.file.read_exact(buffer)
+    }
+}
+
+/// A monitor speaking the VESA DDC/CI protocol on top of an [`I2cTransport`].
+pub struct DdcMonitor<T> {
+    transport: T,
+}
+
+impl<T: I2cTransport> DdcMonitor<T> {
+    const DDC_ADDRESS: u16 = 0x37;
+    const EDID_ADDRESS: u16 = 0x50;
+    const HOST_ADDRESS: u8 = 0x51;
+    /// Destination address (0x37 << 1) used when checksumming host messages.
+    const DISPLAY_WRITE: u8 = 0x6e;
+    /// Virtual host address used when checksumming display replies.
+    const HOST_READ: u8 = 0x50;
+
+    /// Monitors need some time to process a command before answering.
+    const REPLY_DELAY: Duration = Duration::from_millis(40);
+    const WRITE_DELAY: Duration = Duration::from_millis(50);
+
+    pub const VCP_BRIGHTNESS: u8 = 0x10;
+
+    pub fn new(transport: T) -> Self {
+        Self { transport }
+    }
+
+    fn checksum(initial: u8, bytes: &[u8]) -> u8 {
+        bytes.iter().fold(initial, |acc, byte| acc ^ byte)
+    }
+
+    fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        let mut packet = Vec::with_capacity(payload.len() + 3);
+        packet.push(Self::HOST_ADDRESS);
+        packet.push(0x80 | payload.len() as u8);
+        packet.extend_from_slice(payload);
+        packet.push(Self::checksum(Self::DISPLAY_WRITE, &packet));
+
+        self.transport.write(Self::DDC_ADDRESS, &packet)
+    }
+
+    /// Returns the `(current, maximum)` values of a VCP feature.
+    pub fn get_vcp(&mut self, code: u8) -> io::Result<(u16, u16)> {
+        self.send(&[0x01, code])?;
+        std::thread::sleep(Self::REPLY_DELAY);
+
+        let mut reply = [0u8; 11];
+        self.transport.read(Self::DDC_ADDRESS, &mut reply)?;
+
+        let invalid = |reason| io::Error::new(io::ErrorKind::InvalidData, reason);
+
+        if Self::checksum(Self::HOST_READ, &reply[..10]) != reply[10] {
+            return Err(invalid("DDC/CI reply checksum mismatch"));
+        }
+        if reply[1] != 0x88 || reply[2] != 0x02 || reply[4] != code {
+            return Err(invalid("unexpected DDC/CI reply"));
+        }
+        if reply[3] != 0x00 {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "VCP code not supported by the monitor",
+            ));
+        }
+
+        let maximum = u16::from_be_bytes([reply[6], reply[7]]);
+        let current = u16::from_be_bytes([reply[8], reply[9]]);
+
+        Ok((current, maximum))
+    }
+
+    pub fn set_vcp(&mut self, code: u8, value: u16) -> io::Result<()> {
+        let [high, low] = value.to_be_bytes();
+        self.send(&[0x03, code, high, low])?;
+        std::thread::sleep(Self::WRITE_DELAY);
+
+        Ok(())
+    }
+
+    /// Reads the monitor name from the EDID display descriptor, if present.
+    pub fn name(&mut self) -> Option<String> {
+        let mut edid = [0u8; 128];
+        self.transport.write(Self::EDID_ADDRESS, &[0x00]).ok()?;
+        self.transport.read(Self::EDID_ADDRESS, &mut edid).ok()?;
+
+        edid[54..126].chunks(18).find_map(|descriptor| {
+            if descriptor[..3] != [0, 0, 0] || descriptor[3] != 0xfc {
+                return None;
+            }
+
+            let name = String::from_utf8_lossy(&descriptor[5..]);
+            Some(
+                name.split('\n')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned(),
+            )
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MonitorInfo {
+    pub path: PathBuf,
+    pub name: String,
+    pub brightness: u16,
+    pub max_brightness: u16,
+}
+
+pub fn probe<T: I2cTransport>(transport: T, path: PathBuf) -> io::Result<MonitorInfo> {
+    let mut monitor = DdcMonitor::new(transport);
+    let (brightness, max_brightness) = monitor.get_vcp(DdcMonitor::<T>::VCP_BRIGHTNESS)?;
+    let name = monitor
+        .name()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| path.display().to_string());
+
+    Ok(MonitorInfo {
+        path,
+        name,
+        brightness,
+        max_brightness,
+    })
+}
+
+/// Only buses exposed by graphics drivers lead to monitors, poking SMBus
+/// controllers at random addresses is asking for trouble.
+fn is_display_bus(path: &Path) -> bool {
+    let Some(bus) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+
+    match fs::read_to_string(format!("/sys/class/i2c-dev/{bus}/name")) {
+        Ok(name) => !name.contains("SMBus"),
+        Err(_) => false,
+    }
+}
+
+fn scan() -> io::Result<Vec<MonitorInfo>> {
+    let mut monitors = Vec::new();
+
+    for entry in fs::read_dir("/dev")? {
+        let path = entry?.path();
+        let is_i2c = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("i2c-"));
+
+        if !is_i2c || !is_display_bus(&path) {
+            continue;
+        }
+
+        // Most buses don't have a monitor behind them, so failures are expected here.
+        if let Ok(monitor) = I2cDevice::open(&path).and_then(|bus| probe(bus, path.clone())) {
+            monitors.push(monitor);
+        }
+    }
+
+    monitors.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(monitors)
+}
+
+pub async fn scan_monitors() -> Vec<MonitorInfo> {
+    match tokio::task::spawn_blocking(scan).await {
+        Ok(Ok(monitors)) => monitors,
+        Ok(Err(err)) => {
+            eprintln!("Couldn't scan the I2C buses: {err}");
+            Vec::new()
+        }
+        Err(err) => {
+            eprintln!("Couldn't scan the I2C buses: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// Sets the brightness and returns what the monitor is at afterwards, which
+/// is read back from it if the write didn't go through.
+fn apply_brightness<T: I2cTransport>(monitor: &mut DdcMonitor<T>, value: u16) -> io::Result<u16> {
+    match monitor.set_vcp(DdcMonitor::<T>::VCP_BRIGHTNESS, value) {
+        Ok(()) => Ok(value),
+        Err(err) => {
+            eprintln!("Couldn't set the monitor brightness: {err}");
+            let (current, _) = monitor.get_vcp(DdcMonitor::<T>::VCP_BRIGHTNESS)?;
+            Ok(current)
+        }
+    }
+}
+
+/// Returns the brightness the monitor ended up at, or `None` if it can't be
+/// reached at all anymore.
+pub async fn set_brightness(path: PathBuf, value: u16) -> Option<u16> {
+    let result = tokio::task::spawn_blocking(move || {
+        let mut monitor = DdcMonitor::new(I2cDevice::open(&path)?);
+        apply_brightness(&mut monitor, value)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(err)) => {
+            eprintln!("Couldn't read the monitor brightness back: {err}");
+            None
+        }
+        Err(err) => {
+            eprintln!("Couldn't set the monitor brightness: {err}");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An in-memory monitor answering DDC/CI like a real one would.
+    struct FakeMonitor {
+        name: Option<&'static str>,
+        brightness: u16,
+        max_brightness: u16,
+        /// The VCP code asked for by the last "get" request.
+        requested: Option<u8>,
+        fail_writes: bool,
+        corrupt_replies: bool,
+    }
+
+    impl FakeMonitor {
+        fn new() -> Self {
+            Self {
+                name: Some("DELL U2720Q"),
+                brightness: 40,
+                max_brightness: 100,
+                requested: None,
+                fail_writes: false,
+                corrupt_replies: false,
+            }
+        }
+
+        fn edid(&self) -> [u8; 128] {
+            let mut edid = [0u8; 128];
+            if let Some(name) = self.name {
+                // The second descriptor, the first one being timings.
+                let descriptor = &mut edid[72..90];
+                descriptor[3] = 0xfc;
+                descriptor[5..].fill(b' ');
+                descriptor[5..5 + name.len()].copy_from_slice(name.as_bytes());
+                descriptor[5 + name.len()] = b'\n';
+            } else {
+                edid[54] = 0x01;
+            }
+            edid
+        }
+    }
+
+    impl I2cTransport for FakeMonitor {
+        fn write(&mut self, address: u16, data: &[u8]) -> io::Result<()> {
+            if address == 0x50 {
+                return Ok(());
+            }
+            if self.fail_writes && data[2] == 0x03 {
+                return Err(io::Error::new(io::ErrorKind::TimedOut, "no ACK"));
+            }
+
+            match data[2..data.len() - 1] {
+                [0x01, code] => self.requested = Some(code),
+                [0x03, 0x10, high, low] => self.brightness = u16::from_be_bytes([high, low]),
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "unknown")),
+            }
+            Ok(())
+        }
+
+        fn read(&mut self, address: u16, buffer: &mut [u8]) -> io::Result<()> {
+            if address == 0x50 {
+                buffer.copy_from_slice(&self.edid());
+                return Ok(());
+            }
+
+            let code = self.requested.take().unwrap_or_default();
+            let result = if code == 0x10 { 0x00 } else { 0x01 };
+            let [max_high, max_low] = self.max_brightness.to_be_bytes();
+            let [high, low] = self.brightness.to_be_bytes();
+            let mut reply = [
+                0x6e, 0x88, 0x02, result, code, 0x00, max_high, max_low, high, low, 0,
+            ];
+            reply[10] = reply[..10].iter().fold(0x50, |acc, byte| acc ^ byte);
+            if self.corrupt_replies {
+                reply[10] ^= 0xff;
+            }
+
+            buffer.copy_from_slice(&reply);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn probes_name_and_brightness() {
+        let monitor = probe(FakeMonitor::new(), PathBuf::from("/dev/i2c-4")).unwrap();
+
+        assert_eq!(monitor.name, "DELL U2720Q");
+        assert_eq!(monitor.brightness, 40);
+        assert_eq!(monitor.max_brightness, 100);
+    }
+
+    #[test]
+    fn falls_back_to_the_path_without_a_name() {
+        let fake = FakeMonitor {
+            name: None,
+            ..FakeMonitor::new()
+        };
+        let monitor = probe(fake, PathBuf::from("/dev/i2c-4")).unwrap();
+
+        assert_eq!(monitor.name, "/dev/i2c-4");
+    }
+
+    #[test]
+    fn rejects_corrupt_replies() {
+        let mut monitor = DdcMonitor::new(FakeMonitor {
+            corrupt_replies: true,
+            ..FakeMonitor::new()
+        });
+
+        let err = monitor.get_vcp(0x10).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn reports_unsupported_codes() {
+        let mut monitor = DdcMonitor::new(FakeMonitor::new());
+
+        let err = monitor.get_vcp(0x12).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn sets_brightness() {
+        let mut monitor = DdcMonitor::new(FakeMonitor::new());
+
+        assert_eq!(apply_brightness(&mut monitor, 75).unwrap(), 75);
+        assert_eq!(monitor.get_vcp(0x10).unwrap(), (75, 100));
+    }
+
+    #[test]
+    fn failed_writes_report_the_actual_brightness() {
+        let mut monitor = DdcMonitor::new(FakeMonitor {
+            fail_writes: true,
+            ..FakeMonitor::new()
+        });
+
+        assert_eq!(apply_brightness(&mut monitor, 75).unwrap(), 40);
+    }
+}
@@ -80,7 +80,7 @@ where
     I: 'static + std::hash::Hash,
 {
     iced::subscription::run_with_id(
-        id,
+        ("hadess", id),
         async move {
             match event_stream().await {
                 Ok(stream) => stream,
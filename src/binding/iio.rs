@@ -0,0 +1,312 @@
+use iced::futures::{self, FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+
+#[zbus::proxy(
+    interface = "net.hadess.SensorProxy",
+    default_service = "net.hadess.SensorProxy",
+    default_path = "/net/hadess/SensorProxy"
+)]
+pub trait SensorProxy {
+    /// The claim is dropped by the daemon as soon as the connection goes away.
+    fn claim_light(&self) -> zbus::Result<()>;
+
+    fn release_light(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn has_ambient_light(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn light_level(&self) -> zbus::Result<f64>;
+
+    /// Either `lux` or `vendor`, the latter being an arbitrary scale.
+    #[zbus(property)]
+    fn light_level_unit(&self) -> zbus::Result<String>;
+}
+
+#[derive(Debug, Clone)]
+pub enum LightInfo {
+    NotAvailable,
+    Level(f64),
+}
+
+/// Maps ambient light to a brightness fraction (`0.0..=1.0`). Points are
+/// interpolated on a logarithmic lux scale, since that's closer to how the
+/// eye perceives light.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "CurvePoints")]
+pub struct BrightnessCurve {
+    points: Vec<(f64, f64)>,
+}
+
+/// A curve as written in the config, which may be out of order.
+#[derive(Deserialize)]
+struct CurvePoints {
+    points: Vec<(f64, f64)>,
+}
+
+impl From<CurvePoints> for BrightnessCurve {
+    /// Sorts the points by lux and drops the ones sharing a lux value, which
+    /// would otherwise divide by zero when interpolating. Fractions are kept
+    /// within `0.0..=1.0`, as hand-edited ones may not be.
+    fn from(curve: CurvePoints) -> Self {
+        let mut points: Vec<(f64, f64)> = curve
+            .points
+            .into_iter()
+            .filter(|(lux, fraction)| lux.is_finite() && fraction.is_finite())
+            .map(|(lux, fraction)| (lux, fraction.clamp(0.0, 1.0)))
+            .collect();
+        points.sort_by(|(a, _), (b, _)| Self::scale(*a).total_cmp(&Self::scale(*b)));
+        points.dedup_by(|(a, _), (b, _)| Self::scale(*a) == Self::scale(*b));
+
+        if points.is_empty() {
+            return Self::default();
+        }
+
+        Self { points }
+    }
+}
+
+impl Default for BrightnessCurve {
+    fn default() -> Self {
+        Self {
+            points: vec![
+                (0.0, 0.10),
+                (10.0, 0.25),
+                (100.0, 0.45),
+                (1_000.0, 0.75),
+                (10_000.0, 1.00),
+            ],
+        }
+    }
+}
+
+impl BrightnessCurve {
+    /// How far, in decades of lux, a manual correction spreads to its neighbours.
+    const LEARNING_RADIUS: f64 = 1.0;
+
+    fn scale(lux: f64) -> f64 {
+        (lux.max(0.0) + 1.0).log10()
+    }
+
+    pub fn brightness(&self, lux: f64) -> f64 {
+        let x = Self::scale(lux);
+
+        let Some(&(first_lux, first)) = self.points.first() else {
+            return 1.0;
+        };
+        if x <= Self::scale(first_lux) {
+            return first;
+        }
+
+        for window in self.points.windows(2) {
+            let ((lux_a, a), (lux_b, b)) = (window[0], window[1]);
+            let (x_a, x_b) = (Self::scale(lux_a), Self::scale(lux_b));
+
+            if x <= x_b {
+                let t = (x - x_a) / (x_b - x_a);
+                return a + (b - a) * t;
+            }
+        }
+
+        self.points.last().map_or(1.0, |&(_, last)| last)
+    }
+
+    /// Bends the curve towards `fraction` at `lux`, after the user moved the
+    /// slider by hand while auto-brightness was on.
+    pub fn learn(&mut self, lux: f64, fraction: f64) {
+        let x = Self::scale(lux);
+        let delta = fraction.clamp(0.0, 1.0) - self.brightness(lux);
+
+        for (point_lux, point) in &mut self.points {
+            let distance = (Self::scale(*point_lux) - x).abs();
+            let weight = (1.0 - distance / Self::LEARNING_RADIUS).max(0.0);
+            *point = (*point + delta * weight).clamp(0.0, 1.0);
+        }
+
+        // Brighter rooms should never end up with a dimmer screen.
+        for i in 1..self.points.len() {
+            if self.points[i].1 < self.points[i - 1].1 {
+                self.points[i].1 = self.points[i - 1].1;
+            }
+        }
+    }
+}
+
+async fn connection() -> zbus::Result<SensorProxyProxy<'static>> {
+    let connection = zbus::Connection::system().await?;
+    let sensor_proxy = SensorProxyProxy::new(&connection).await?;
+
+    Ok(sensor_proxy)
+}
+
+/// Claims the light sensor on `sensor_proxy` and follows its readings. The
+/// proxy is taken by value so a private bus can be used instead of the system one.
+pub async fn event_stream(
+    sensor_proxy: SensorProxyProxy<'static>,
+) -> zbus::Result<impl futures::Stream<Item = LightInfo>> {
+    if !sensor_proxy.has_ambient_light().await? {
+        return Ok(futures::stream::once(async { LightInfo::NotAvailable }).left_stream());
+    }
+
+    sensor_proxy.claim_light().await?;
+
+    let initial = LightInfo::Level(sensor_proxy.light_level().await?);
+    let stream = sensor_proxy.receive_light_level_changed().await;
+
+    // A reading that can't be decoded is skipped rather than taken as
+    // darkness, which would dim the screen all the way.
+    let levels = stream.filter_map(|changed| async move { changed.get().await.ok() });
+
+    Ok(futures::stream::once(async { initial })
+        .chain(levels.map(LightInfo::Level))
+        .right_stream())
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<LightInfo>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("iio", id),
+        async move {
+            let stream = match connection().await {
+                Ok(sensor_proxy) => event_stream(sensor_proxy).await,
+                Err(err) => Err(err),
+            };
+
+            match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("An error has ocurred: {err}");
+                    futures::future::pending().await
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::testing::{self, PrivateBus};
+
+    #[test]
+    fn brightness_follows_the_curve() {
+        let curve = BrightnessCurve::default();
+
+        assert_eq!(curve.brightness(0.0), 0.10);
+        assert_eq!(curve.brightness(100.0), 0.45);
+        assert_eq!(curve.brightness(50_000.0), 1.00);
+
+        // Halfway between 10 and 100 lux on the log scale.
+        let halfway = 10f64.powf((11f64.log10() + 101f64.log10()) / 2.0) - 1.0;
+        assert!((curve.brightness(halfway) - 0.35).abs() < 1e-9);
+    }
+
+    #[test]
+    fn learning_keeps_the_curve_increasing() {
+        let mut curve = BrightnessCurve::default();
+        curve.learn(10.0, 0.9);
+
+        assert!((curve.brightness(10.0) - 0.9).abs() < 1e-9);
+        assert!(curve.points.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    }
+
+    #[test]
+    fn loaded_points_are_sorted_and_deduplicated() {
+        let curve: BrightnessCurve =
+            serde_json::from_str(r#"{"points": [[100, 0.5], [0, 0.1], [100, 0.9], [-5, 0.2]]}"#)
+                .unwrap();
+
+        assert_eq!(curve.points, vec![(0.0, 0.1), (100.0, 0.5)]);
+        assert!(curve.brightness(50.0).is_finite());
+    }
+
+    #[test]
+    fn loaded_fractions_are_clamped() {
+        let curve: BrightnessCurve =
+            serde_json::from_str(r#"{"points": [[0, -0.5], [100, 0.5], [1000, 1.5]]}"#).unwrap();
+
+        assert_eq!(curve.points, vec![(0.0, 0.0), (100.0, 0.5), (1000.0, 1.0)]);
+        assert_eq!(curve.brightness(5_000.0), 1.0);
+    }
+
+    #[test]
+    fn an_empty_curve_falls_back_to_the_default() {
+        let curve: BrightnessCurve = serde_json::from_str(r#"{"points": []}"#).unwrap();
+
+        assert_eq!(curve.points, BrightnessCurve::default().points);
+    }
+
+    struct MockSensorProxy {
+        light_level: f64,
+    }
+
+    #[zbus::interface(name = "net.hadess.SensorProxy")]
+    impl MockSensorProxy {
+        fn claim_light(&self) {}
+
+        fn release_light(&self) {}
+
+        #[zbus(property)]
+        fn has_ambient_light(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn light_level(&self) -> f64 {
+            self.light_level
+        }
+
+        #[zbus(property)]
+        fn light_level_unit(&self) -> &str {
+            "lux"
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_the_light_level() {
+        let bus = PrivateBus::start();
+        let service = bus.connect().await;
+        service
+            .object_server()
+            .at(
+                "/net/hadess/SensorProxy",
+                MockSensorProxy { light_level: 12.0 },
+            )
+            .await
+            .unwrap();
+        service
+            .request_name("net.hadess.SensorProxy")
+            .await
+            .unwrap();
+
+        let client = bus.connect().await;
+        let sensor_proxy = SensorProxyProxy::new(&client).await.unwrap();
+        let mut stream = Box::pin(event_stream(sensor_proxy).await.unwrap());
+
+        assert!(matches!(
+            testing::next(&mut stream).await,
+            LightInfo::Level(12.0)
+        ));
+
+        let sensor = service
+            .object_server()
+            .interface::<_, MockSensorProxy>("/net/hadess/SensorProxy")
+            .await
+            .unwrap();
+        sensor.get_mut().await.light_level = 340.0;
+        sensor
+            .get()
+            .await
+            .light_level_changed(sensor.signal_context())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            testing::next(&mut stream).await,
+            LightInfo::Level(340.0)
+        ));
+    }
+}
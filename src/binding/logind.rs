@@ -8,6 +8,65 @@ use std::io;
 )]
 trait LogindSession {
     fn set_brightness(&self, subsystem: &str, name: &str, brightness: u32) -> zbus::Result<()>;
+
+    fn lock(&self) -> zbus::Result<()>;
+
+    fn terminate(&self) -> zbus::Result<()>;
+
+    #[zbus(signal, name = "Lock")]
+    fn lock_requested(&self) -> zbus::Result<()>;
+
+    #[zbus(signal, name = "Unlock")]
+    fn unlock_requested(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn locked_hint(&self) -> zbus::Result<bool>;
+}
+
+/// `(what, who, why, mode, uid, pid)` as returned by `ListInhibitors`.
+type InhibitorEntry = (String, String, String, String, u32, u32);
+
+#[zbus::proxy(
+    default_service = "org.freedesktop.login1",
+    interface = "org.freedesktop.login1.Manager",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LogindManager {
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+
+    /// All `Can*` methods answer `yes`, `no`, `challenge` or `na`.
+    fn can_suspend(&self) -> zbus::Result<String>;
+
+    fn can_hibernate(&self) -> zbus::Result<String>;
+
+    fn can_reboot(&self) -> zbus::Result<String>;
+
+    fn can_power_off(&self) -> zbus::Result<String>;
+
+    /// The lock is held for as long as the returned descriptor stays open.
+    fn inhibit(
+        &self,
+        what: &str,
+        who: &str,
+        why: &str,
+        mode: &str,
+    ) -> zbus::Result<zbus::zvariant::OwnedFd>;
+
+    fn list_inhibitors(&self) -> zbus::Result<Vec<InhibitorEntry>>;
+
+    fn get_session(&self, session_id: &str) -> zbus::Result<zbus::zvariant::OwnedObjectPath>;
+
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn prepare_for_shutdown(&self, start: bool) -> zbus::Result<()>;
 }
 
 async fn connection() -> zbus::Result<LogindSessionProxy<'static>> {
@@ -17,6 +76,226 @@ async fn connection() -> zbus::Result<LogindSessionProxy<'static>> {
     Ok(logind)
 }
 
+async fn manager() -> zbus::Result<LogindManagerProxy<'static>> {
+    let conn = zbus::Connection::system().await?;
+    let manager = LogindManagerProxy::new(&conn).await?;
+
+    Ok(manager)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerAction {
+    Lock,
+    LogOut,
+    Suspend,
+    Hibernate,
+    Reboot,
+    PowerOff,
+}
+
+impl PowerAction {
+    /// Actions that close every application ask for confirmation first.
+    pub fn is_destructive(self) -> bool {
+        matches!(self, Self::LogOut | Self::Reboot | Self::PowerOff)
+    }
+}
+
+impl std::fmt::Display for PowerAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PowerAction::Lock => write!(f, "Lock"),
+            PowerAction::LogOut => write!(f, "Log Out"),
+            PowerAction::Suspend => write!(f, "Suspend"),
+            PowerAction::Hibernate => write!(f, "Hibernate"),
+            PowerAction::Reboot => write!(f, "Restart"),
+            PowerAction::PowerOff => write!(f, "Power Off"),
+        }
+    }
+}
+
+/// `challenge` means polkit will ask for credentials, which is still available.
+fn is_allowed(answer: zbus::Result<String>) -> bool {
+    matches!(answer.as_deref(), Ok("yes" | "challenge"))
+}
+
+pub async fn power_actions() -> Vec<PowerAction> {
+    let mut actions = vec![PowerAction::Lock, PowerAction::LogOut];
+
+    let manager = match manager().await {
+        Ok(manager) => manager,
+        Err(err) => {
+            eprintln!("Couldn't connect to logind: {err}");
+            return actions;
+        }
+    };
+
+    if is_allowed(manager.can_suspend().await) {
+        actions.push(PowerAction::Suspend);
+    }
+    if is_allowed(manager.can_hibernate().await) {
+        actions.push(PowerAction::Hibernate);
+    }
+    if is_allowed(manager.can_reboot().await) {
+        actions.push(PowerAction::Reboot);
+    }
+    if is_allowed(manager.can_power_off().await) {
+        actions.push(PowerAction::PowerOff);
+    }
+
+    actions
+}
+
+async fn try_perform(action: PowerAction) -> zbus::Result<()> {
+    match action {
+        PowerAction::Lock => connection().await?.lock().await,
+        PowerAction::LogOut => connection().await?.terminate().await,
+        PowerAction::Suspend => manager().await?.suspend(true).await,
+        PowerAction::Hibernate => manager().await?.hibernate(true).await,
+        PowerAction::Reboot => manager().await?.reboot(true).await,
+        PowerAction::PowerOff => manager().await?.power_off(true).await,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionEvent {
+    Sleeping,
+    Resumed,
+    ShuttingDown,
+    Locked,
+    Unlocked,
+}
+
+async fn session_event_stream() -> zbus::Result<impl futures::Stream<Item = SessionEvent>> {
+    let conn = zbus::Connection::system().await?;
+    let manager = LogindManagerProxy::new(&conn).await?;
+
+    // Signals are emitted on the real session path, never on `session/auto`.
+    let session_path = manager.get_session("auto").await?;
+    let session = LogindSessionProxy::builder(&conn)
+        .path(session_path)?
+        .build()
+        .await?;
+
+    let sleep = manager.receive_prepare_for_sleep().await?.map(|signal| {
+        match signal.args().map(|args| args.start) {
+            Ok(false) => SessionEvent::Resumed,
+            _ => SessionEvent::Sleeping,
+        }
+    });
+    let shutdown = manager
+        .receive_prepare_for_shutdown()
+        .await?
+        .map(|_| SessionEvent::ShuttingDown);
+    let lock = session
+        .receive_lock_requested()
+        .await?
+        .map(|_| SessionEvent::Locked);
+    let unlock = session
+        .receive_unlock_requested()
+        .await?
+        .map(|_| SessionEvent::Unlocked);
+
+    // The session may already be locked when we start, e.g. after a restart.
+    let initial = match session.locked_hint().await {
+        Ok(true) => SessionEvent::Locked,
+        Ok(false) => SessionEvent::Unlocked,
+        Err(err) => {
+            eprintln!("Couldn't read whether the session is locked: {err}");
+            SessionEvent::Unlocked
+        }
+    };
+
+    Ok(futures::stream::once(async move { initial })
+        .chain(futures::stream_select!(sleep, shutdown, lock, unlock)))
+}
+
+pub fn session_subscription<T>(id: T) -> iced::Subscription<SessionEvent>
+where
+    T: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("logind-session", id),
+        async move {
+            match session_event_stream().await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("An error has ocurred: {err}");
+                    futures::future::pending().await
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+/// Keeps the system awake until the last clone is dropped.
+#[derive(Debug, Clone)]
+pub struct InhibitorLock {
+    _fd: std::sync::Arc<zbus::zvariant::OwnedFd>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Inhibitor {
+    pub what: String,
+    pub who: String,
+    pub why: String,
+    pub mode: String,
+    pub pid: u32,
+}
+
+const INHIBITOR_NAME: &str = "wawi";
+
+pub async fn inhibit_idle() -> Option<InhibitorLock> {
+    let result = async {
+        manager()
+            .await?
+            .inhibit("idle:sleep", INHIBITOR_NAME, "Caffeine mode is on", "block")
+            .await
+    }
+    .await;
+
+    match result {
+        Ok(fd) => Some(InhibitorLock {
+            _fd: std::sync::Arc::new(fd),
+        }),
+        Err(err) => {
+            eprintln!("Couldn't take the inhibitor lock: {err}");
+            None
+        }
+    }
+}
+
+/// Inhibitors held by other applications, ours is left out.
+pub async fn inhibitors() -> Vec<Inhibitor> {
+    let result = async { manager().await?.list_inhibitors().await }.await;
+
+    match result {
+        Ok(inhibitors) => inhibitors
+            .into_iter()
+            .filter(|(_, who, ..)| who != INHIBITOR_NAME)
+            .map(|(what, who, why, mode, _uid, pid)| Inhibitor {
+                what,
+                who,
+                why,
+                mode,
+                pid,
+            })
+            .collect(),
+        Err(err) => {
+            eprintln!("Couldn't list the inhibitors: {err}");
+            Vec::new()
+        }
+    }
+}
+
+pub async fn perform(action: PowerAction) -> PowerAction {
+    if let Err(err) = try_perform(action).await {
+        eprintln!("Couldn't perform {action}: {err}");
+    }
+
+    action
+}
+
 /// [`subsystem`] can be either `backlight` or `leds`.
 #[derive(Debug, Clone)]
 pub struct BrightnessDevice {
@@ -213,7 +492,7 @@ where
     T: 'static + std::hash::Hash,
 {
     iced::subscription::run_with_id(
-        id,
+        ("logind-display", id),
         async move {
             match event_stream().await {
                 Ok(sst) => sst,
@@ -1,3 +1,23 @@
+//! Every subscription is keyed by a tag of its own along with the caller's
+//! id, as iced only hashes the id and its type: two bindings subscribed with
+//! the same id would otherwise be taken for one and the second never started.
+
+pub mod bluez;
+pub mod ddc;
 pub mod hadess;
+pub mod iio;
 pub mod logind;
+pub mod mounts;
+pub mod mpris;
+pub mod networkmanager;
+pub mod notifications;
+pub mod platform_profile;
+pub mod pulse;
+pub mod rfkill;
+pub mod sensors;
+pub mod system_monitor;
+pub mod udisks;
 pub mod upower;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,160 @@
+use std::{ffi::CString, fs, io, os::unix::ffi::OsStrExt, path::Path};
+
+const MOUNTS_PATH: &str = "/proc/self/mounts";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filesystem {
+    pub mount_point: String,
+    /// Bytes.
+    pub used: u64,
+    /// Bytes.
+    pub total: u64,
+}
+
+impl Filesystem {
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.used as f64 / self.total as f64
+        }
+    }
+}
+
+/// Spaces and such are escaped as `\040` octal sequences in the mount table.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let octal = bytes
+            .get(index + 1..index + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+
+        match octal {
+            Some(byte) if bytes[index] == b'\\' => {
+                unescaped.push(byte);
+                index += 4;
+            }
+            _ => {
+                unescaped.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+/// Mount points of block device backed mounts, the first one of each device
+/// only, since bind mounts and subvolumes share their device's space.
+fn parse_mounts(mounts: &str) -> Vec<String> {
+    let mut devices: Vec<&str> = Vec::new();
+    let mut mount_points = Vec::new();
+
+    for line in mounts.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(device), Some(mount_point)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+
+        // Loop devices are mostly read-only images, e.g. snaps, always full.
+        if !device.starts_with("/dev/") || device.starts_with("/dev/loop") {
+            continue;
+        }
+        if devices.contains(&device) {
+            continue;
+        }
+
+        devices.push(device);
+        mount_points.push(unescape(mount_point));
+    }
+
+    mount_points
+}
+
+/// Used and total bytes of the filesystem mounted at `mount_point`.
+fn usage(mount_point: &Path) -> io::Result<(u64, u64)> {
+    let path = CString::new(mount_point.as_os_str().as_bytes())?;
+    // SAFETY: `statvfs` is plain old data, all zeroes is a valid value.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+
+    // SAFETY: `path` is NUL terminated and `stat` is a valid out pointer.
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let block_size = stat.f_frsize as u64;
+    let total = stat.f_blocks as u64 * block_size;
+    let free = stat.f_bfree as u64 * block_size;
+
+    Ok((total.saturating_sub(free), total))
+}
+
+fn read_filesystems() -> io::Result<Vec<Filesystem>> {
+    let mounts = fs::read_to_string(MOUNTS_PATH)?;
+
+    Ok(parse_mounts(&mounts)
+        .into_iter()
+        .filter_map(|mount_point| {
+            // Gone by now, or something we're not allowed to look at.
+            let (used, total) = usage(Path::new(&mount_point)).ok()?;
+
+            Some(Filesystem {
+                mount_point,
+                used,
+                total,
+            })
+        })
+        .collect())
+}
+
+/// Mounted filesystems along with how full they are.
+pub async fn filesystems() -> Vec<Filesystem> {
+    let result = tokio::task::spawn_blocking(read_filesystems).await;
+
+    match result {
+        Ok(Ok(filesystems)) => filesystems,
+        Ok(Err(err)) => {
+            eprintln!("Couldn't read {MOUNTS_PATH}: {err}");
+            Vec::new()
+        }
+        Err(err) => {
+            eprintln!("Couldn't read {MOUNTS_PATH}: {err}");
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn octal_escapes_are_unescaped() {
+        assert_eq!(unescape(r"/media/My\040Drive"), "/media/My Drive");
+        assert_eq!(unescape(r"/tab\011and\134slash"), "/tab\tand\\slash");
+        // Not an escape, too short or not octal.
+        assert_eq!(unescape(r"/odd\04"), r"/odd\04");
+        assert_eq!(unescape(r"/odd\089"), r"/odd\089");
+    }
+
+    #[test]
+    fn only_the_first_mount_of_each_device_counts() {
+        let mounts = "\
+/dev/nvme0n1p2 / btrfs rw,relatime 0 0
+proc /proc proc rw,nosuid 0 0
+tmpfs /tmp tmpfs rw 0 0
+/dev/nvme0n1p2 /home btrfs rw,relatime,subvol=/home 0 0
+/dev/loop0 /snap/core/1 squashfs ro 0 0
+/dev/sda1 /run/media/user/USB\\040Stick vfat rw 0 0
+";
+
+        assert_eq!(
+            parse_mounts(mounts),
+            vec!["/".to_owned(), "/run/media/user/USB Stick".to_owned()]
+        );
+    }
+}
@@ -0,0 +1,486 @@
+use iced::futures::{self, FutureExt, StreamExt};
+use std::{collections::HashMap, path::PathBuf};
+use zbus::zvariant::{Array, ObjectPath, OwnedObjectPath, OwnedValue};
+
+const BUS_NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait MediaPlayer2 {
+    #[zbus(property)]
+    fn identity(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.mpris.MediaPlayer2.Player",
+    default_path = "/org/mpris/MediaPlayer2"
+)]
+trait Player {
+    fn play_pause(&self) -> zbus::Result<()>;
+
+    fn next(&self) -> zbus::Result<()>;
+
+    fn previous(&self) -> zbus::Result<()>;
+
+    /// `offset` is relative to the current position, in microseconds.
+    fn seek(&self, offset: i64) -> zbus::Result<()>;
+
+    fn set_position(&self, track_id: &ObjectPath<'_>, position: i64) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn metadata(&self) -> zbus::Result<HashMap<String, OwnedValue>>;
+
+    /// Never announced through `PropertiesChanged`, only through `Seeked`.
+    #[zbus(property(emits_changed_signal = "false"))]
+    fn position(&self) -> zbus::Result<i64>;
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn can_seek(&self) -> zbus::Result<bool>;
+
+    #[zbus(signal)]
+    fn seeked(&self, position: i64) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PlaybackStatus {
+    Playing,
+    Paused,
+    #[default]
+    Stopped,
+}
+
+impl From<String> for PlaybackStatus {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "Playing" => Self::Playing,
+            "Paused" => Self::Paused,
+            _ => Self::Stopped,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlayerInfo {
+    pub bus_name: String,
+    pub identity: String,
+    pub status: PlaybackStatus,
+    pub title: String,
+    pub artist: String,
+    /// Only local artwork (`file://`) is supported.
+    pub art: Option<PathBuf>,
+    pub track_id: Option<OwnedObjectPath>,
+    /// Microseconds, like everything else in MPRIS.
+    pub length: i64,
+    pub position: i64,
+    pub can_go_next: bool,
+    pub can_go_previous: bool,
+    pub can_seek: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum MprisInfo {
+    Players(Vec<PlayerInfo>),
+}
+
+#[derive(Debug, Clone)]
+pub enum PlayerAction {
+    PlayPause,
+    Next,
+    Previous,
+    SetPosition(i64),
+}
+
+fn metadata_string(metadata: &HashMap<String, OwnedValue>, key: &str) -> Option<String> {
+    metadata.get(key)?.downcast_ref::<String>().ok()
+}
+
+/// `xesam:artist` is a list, but some players send a single string anyway.
+fn metadata_artist(metadata: &HashMap<String, OwnedValue>) -> String {
+    let Some(value) = metadata.get("xesam:artist") else {
+        return String::new();
+    };
+
+    if let Ok(artists) = value.downcast_ref::<&Array>() {
+        return artists
+            .inner()
+            .iter()
+            .filter_map(|artist| artist.downcast_ref::<String>().ok())
+            .collect::<Vec<_>>()
+            .join(", ");
+    }
+
+    value.downcast_ref::<String>().unwrap_or_default()
+}
+
+/// Spec says `x`, but plenty of players send `t` or even `i`.
+fn metadata_length(metadata: &HashMap<String, OwnedValue>) -> i64 {
+    let Some(value) = metadata.get("mpris:length") else {
+        return 0;
+    };
+
+    value
+        .downcast_ref::<i64>()
+        .or_else(|_| value.downcast_ref::<u64>().map(|length| length as i64))
+        .or_else(|_| value.downcast_ref::<i32>().map(i64::from))
+        .unwrap_or_default()
+}
+
+async fn player_info(conn: &zbus::Connection, bus_name: String) -> zbus::Result<PlayerInfo> {
+    let media_player = MediaPlayer2Proxy::builder(conn)
+        .destination(bus_name.clone())?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+    let player = PlayerProxy::builder(conn)
+        .destination(bus_name.clone())?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+
+    let metadata = player.metadata().await.unwrap_or_default();
+    let identity = match media_player.identity().await {
+        Ok(identity) => identity,
+        Err(_) => bus_name.trim_start_matches(BUS_NAME_PREFIX).to_owned(),
+    };
+
+    Ok(PlayerInfo {
+        identity,
+        status: PlaybackStatus::from(player.playback_status().await?),
+        title: metadata_string(&metadata, "xesam:title").unwrap_or_default(),
+        artist: metadata_artist(&metadata),
+        art: metadata_string(&metadata, "mpris:artUrl")
+            .and_then(|url| url.strip_prefix("file://").map(PathBuf::from)),
+        track_id: metadata
+            .get("mpris:trackid")
+            .and_then(|value| value.downcast_ref::<ObjectPath>().ok())
+            .map(OwnedObjectPath::from),
+        length: metadata_length(&metadata),
+        position: player.position().await.unwrap_or_default(),
+        can_go_next: player.can_go_next().await.unwrap_or_default(),
+        can_go_previous: player.can_go_previous().await.unwrap_or_default(),
+        can_seek: player.can_seek().await.unwrap_or_default(),
+        bus_name,
+    })
+}
+
+async fn players(conn: &zbus::Connection) -> Vec<PlayerInfo> {
+    let names = match zbus::fdo::DBusProxy::new(conn).await {
+        Ok(dbus) => dbus.list_names().await.unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+
+    let mut players = Vec::new();
+    for name in names {
+        if !name.starts_with(BUS_NAME_PREFIX) {
+            continue;
+        }
+
+        match player_info(conn, name.to_string()).await {
+            Ok(player) => players.push(player),
+            Err(err) => eprintln!("Couldn't read the {name} player: {err}"),
+        }
+    }
+
+    players.sort_by(|a, b| a.bus_name.cmp(&b.bus_name));
+    players
+}
+
+async fn signal_stream(
+    conn: &zbus::Connection,
+    rule: zbus::MatchRule<'static>,
+) -> zbus::Result<zbus::MessageStream> {
+    zbus::MessageStream::for_match_rule(rule, conn, None).await
+}
+
+/// Follows every MPRIS player on `conn`. The connection is a parameter so a
+/// private bus can stand in for the session one.
+pub async fn event_stream(
+    conn: zbus::Connection,
+) -> zbus::Result<impl futures::Stream<Item = MprisInfo>> {
+    let properties_changed = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .path(OBJECT_PATH)?
+        .build();
+    let seeked = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.mpris.MediaPlayer2.Player")?
+        .member("Seeked")?
+        .path(OBJECT_PATH)?
+        .build();
+    let owner_changed = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.freedesktop.DBus")?
+        .interface("org.freedesktop.DBus")?
+        .member("NameOwnerChanged")?
+        .arg0ns("org.mpris.MediaPlayer2")?
+        .build();
+
+    let signals = futures::stream_select!(
+        signal_stream(&conn, properties_changed).await?,
+        signal_stream(&conn, seeked).await?,
+        signal_stream(&conn, owner_changed).await?,
+    );
+
+    let initial = MprisInfo::Players(players(&conn).await);
+
+    Ok(
+        futures::stream::once(async { initial }).chain(signals.then(move |_| {
+            let conn = conn.clone();
+            async move { MprisInfo::Players(players(&conn).await) }
+        })),
+    )
+}
+
+async fn try_control(
+    conn: &zbus::Connection,
+    bus_name: String,
+    track_id: Option<OwnedObjectPath>,
+    action: &PlayerAction,
+) -> zbus::Result<()> {
+    let player = PlayerProxy::builder(conn)
+        .destination(bus_name)?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+
+    match action {
+        PlayerAction::PlayPause => player.play_pause().await,
+        PlayerAction::Next => player.next().await,
+        PlayerAction::Previous => player.previous().await,
+        PlayerAction::SetPosition(position) => match track_id {
+            Some(track_id) => player.set_position(&track_id, *position).await,
+            None => {
+                let current = player.position().await?;
+                player.seek(position - current).await
+            }
+        },
+    }
+}
+
+pub async fn control(
+    bus_name: String,
+    track_id: Option<OwnedObjectPath>,
+    action: PlayerAction,
+) -> PlayerAction {
+    let result = async {
+        let conn = zbus::Connection::session().await?;
+        try_control(&conn, bus_name, track_id, &action).await
+    }
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Couldn't control the media player: {err}");
+    }
+
+    action
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<MprisInfo>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("mpris", id),
+        async move {
+            let stream = match zbus::Connection::session().await {
+                Ok(conn) => event_stream(conn).await,
+                Err(err) => Err(err),
+            };
+
+            match stream {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("An error has ocurred: {err}");
+                    futures::future::pending().await
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::testing::{self, PrivateBus};
+    use zbus::zvariant::Value;
+
+    fn owned(value: Value<'_>) -> OwnedValue {
+        value.try_to_owned().unwrap()
+    }
+
+    #[test]
+    fn artist_is_read_as_a_list_or_a_string() {
+        let list = HashMap::from([(
+            "xesam:artist".to_string(),
+            owned(Value::from(vec!["Daft Punk", "Pharrell"])),
+        )]);
+        let single = HashMap::from([("xesam:artist".to_string(), owned(Value::from("Air")))]);
+
+        assert_eq!(metadata_artist(&list), "Daft Punk, Pharrell");
+        assert_eq!(metadata_artist(&single), "Air");
+        assert_eq!(metadata_artist(&HashMap::new()), "");
+    }
+
+    #[test]
+    fn length_accepts_any_integer_type() {
+        for value in [
+            Value::from(90_000_000i64),
+            Value::from(90_000_000u64),
+            Value::from(90_000_000i32),
+        ] {
+            let metadata = HashMap::from([("mpris:length".to_string(), owned(value))]);
+            assert_eq!(metadata_length(&metadata), 90_000_000);
+        }
+    }
+
+    struct FakeMediaPlayer;
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2")]
+    impl FakeMediaPlayer {
+        #[zbus(property)]
+        fn identity(&self) -> &str {
+            "Fake Player"
+        }
+    }
+
+    struct FakePlayer {
+        status: String,
+    }
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl FakePlayer {
+        async fn play_pause(&mut self, #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>) {
+            self.status = match self.status.as_str() {
+                "Playing" => "Paused",
+                _ => "Playing",
+            }
+            .to_string();
+            let _ = self.playback_status_changed(&ctxt).await;
+        }
+
+        #[zbus(property)]
+        fn playback_status(&self) -> &str {
+            &self.status
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            HashMap::from([
+                ("xesam:title".to_string(), owned(Value::from("Song"))),
+                ("xesam:artist".to_string(), owned(Value::from(vec!["Band"]))),
+                ("mpris:length".to_string(), owned(Value::from(1_000_000i64))),
+            ])
+        }
+
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            0
+        }
+
+        #[zbus(property)]
+        fn can_go_next(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_go_previous(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn can_seek(&self) -> bool {
+            true
+        }
+    }
+
+    async fn start_player(bus: &PrivateBus) -> zbus::Connection {
+        let conn = bus.connect().await;
+        conn.object_server()
+            .at(OBJECT_PATH, FakeMediaPlayer)
+            .await
+            .unwrap();
+        conn.object_server()
+            .at(
+                OBJECT_PATH,
+                FakePlayer {
+                    status: "Paused".to_string(),
+                },
+            )
+            .await
+            .unwrap();
+        conn.request_name("org.mpris.MediaPlayer2.fake")
+            .await
+            .unwrap();
+
+        conn
+    }
+
+    /// The next list of players, skipping those sent for unrelated signals.
+    async fn next_players<S>(
+        stream: &mut S,
+        matches: impl Fn(&[PlayerInfo]) -> bool,
+    ) -> Vec<PlayerInfo>
+    where
+        S: futures::Stream<Item = MprisInfo> + Unpin,
+    {
+        loop {
+            let MprisInfo::Players(players) = testing::next(stream).await;
+            if matches(&players) {
+                return players;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn follows_players_coming_and_going() {
+        let bus = PrivateBus::start();
+        let client = bus.connect().await;
+        let mut stream = Box::pin(event_stream(client.clone()).await.unwrap());
+
+        let MprisInfo::Players(players) = testing::next(&mut stream).await;
+        assert!(players.is_empty());
+
+        let player = start_player(&bus).await;
+        let players = next_players(&mut stream, |players| !players.is_empty()).await;
+        assert_eq!(players[0].bus_name, "org.mpris.MediaPlayer2.fake");
+        assert_eq!(players[0].identity, "Fake Player");
+        assert_eq!(players[0].status, PlaybackStatus::Paused);
+        assert_eq!(players[0].title, "Song");
+        assert_eq!(players[0].artist, "Band");
+        assert_eq!(players[0].length, 1_000_000);
+        assert!(players[0].can_go_next && !players[0].can_go_previous);
+
+        try_control(
+            &client,
+            "org.mpris.MediaPlayer2.fake".to_string(),
+            None,
+            &PlayerAction::PlayPause,
+        )
+        .await
+        .unwrap();
+        next_players(&mut stream, |players| {
+            players
+                .first()
+                .is_some_and(|player| player.status == PlaybackStatus::Playing)
+        })
+        .await;
+
+        drop(player);
+        next_players(&mut stream, <[PlayerInfo]>::is_empty).await;
+    }
+}
@@ -0,0 +1,1633 @@
+use iced::futures::{self, FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+use zbus::zvariant::{Array, ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager"
+)]
+trait NetworkManager {
+    fn get_devices(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn deactivate_connection(&self, active_connection: &ObjectPath<'_>) -> zbus::Result<()>;
+
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    /// Settings left out are filled in by NetworkManager from the access point.
+    fn add_and_activate_connection(
+        &self,
+        connection: HashMap<&str, HashMap<&str, Value<'_>>>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> zbus::Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+    #[zbus(property)]
+    fn wireless_enabled(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn set_wireless_enabled(&self, value: bool) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn wireless_hardware_enabled(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn active_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait ActiveConnection {
+    /// The saved profile this was activated from.
+    #[zbus(property)]
+    fn connection(&self) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn state(&self) -> zbus::Result<u32>;
+
+    /// Of the profile, which unlike its object path survives NetworkManager
+    /// restarting.
+    #[zbus(property)]
+    fn uuid(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Device {
+    #[zbus(property)]
+    fn device_type(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn interface(&self) -> zbus::Result<String>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wired",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Wired {
+    /// Mb/s, 0 when unknown.
+    #[zbus(property)]
+    fn speed(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn carrier(&self) -> zbus::Result<bool>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait Wireless {
+    fn get_all_access_points(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+
+    fn request_scan(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn active_access_point(&self) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait AccessPoint {
+    #[zbus(property)]
+    fn ssid(&self) -> zbus::Result<Vec<u8>>;
+
+    /// Percentage, from 0 to 100.
+    #[zbus(property)]
+    fn strength(&self) -> zbus::Result<u8>;
+
+    #[zbus(property)]
+    fn flags(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> zbus::Result<u32>;
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> zbus::Result<u32>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_service = "org.freedesktop.NetworkManager",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait Settings {
+    fn list_connections(&self) -> zbus::Result<Vec<OwnedObjectPath>>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    default_service = "org.freedesktop.NetworkManager"
+)]
+trait SettingsConnection {
+    fn get_settings(&self) -> zbus::Result<HashMap<String, HashMap<String, OwnedValue>>>;
+
+    /// Replaces every setting of the profile.
+    fn update(&self, properties: HashMap<&str, HashMap<&str, Value<'_>>>) -> zbus::Result<()>;
+}
+
+/// `NMDeviceType` values we care about.
+const DEVICE_TYPE_ETHERNET: u32 = 1;
+const DEVICE_TYPE_WIFI: u32 = 2;
+
+/// Mobile broadband is ModemManager's business, NetworkManager only dials.
+const MODEM_MANAGER: &str = "org.freedesktop.ModemManager1";
+const MODEM_INTERFACE: &str = "org.freedesktop.ModemManager1.Modem";
+const MODEM_3GPP_INTERFACE: &str = "org.freedesktop.ModemManager1.Modem.Modem3gpp";
+
+/// Name of the profile we share the connection through.
+const HOTSPOT_ID: &str = "Hotspot";
+
+/// `NMActiveConnectionState` values.
+const ACTIVE_STATE_ACTIVATING: u32 = 1;
+const ACTIVE_STATE_ACTIVATED: u32 = 2;
+const ACTIVE_STATE_DEACTIVATING: u32 = 3;
+const ACTIVE_STATE_DEACTIVATED: u32 = 4;
+
+/// `NM80211ApFlags` and `NM80211ApSecurityFlags` bits.
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+const AP_SEC_KEY_MGMT_PSK: u32 = 0x100;
+const AP_SEC_KEY_MGMT_802_1X: u32 = 0x200;
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Security {
+    Open,
+    Wep,
+    /// WPA or WPA2 with a pre-shared key.
+    Wpa,
+    Wpa3,
+    Enterprise,
+}
+
+impl Security {
+    fn from_flags(flags: u32, wpa_flags: u32, rsn_flags: u32) -> Self {
+        let key_mgmt = wpa_flags | rsn_flags;
+
+        if key_mgmt & AP_SEC_KEY_MGMT_802_1X != 0 {
+            Security::Enterprise
+        } else if key_mgmt & AP_SEC_KEY_MGMT_PSK != 0 {
+            Security::Wpa
+        } else if key_mgmt & AP_SEC_KEY_MGMT_SAE != 0 {
+            Security::Wpa3
+        } else if flags & AP_FLAGS_PRIVACY != 0 {
+            Security::Wep
+        } else {
+            Security::Open
+        }
+    }
+
+    /// `802-11-wireless-security` settings for a new connection.
+    fn settings(self, passphrase: &str) -> HashMap<&'static str, Value<'_>> {
+        let mut settings = HashMap::new();
+
+        match self {
+            Security::Open | Security::Enterprise => {}
+            Security::Wep => {
+                settings.insert("key-mgmt", Value::from("none"));
+                settings.insert("wep-key0", Value::from(passphrase));
+                settings.insert("wep-key-type", Value::from(wep_key_type(passphrase)));
+            }
+            Security::Wpa => {
+                settings.insert("key-mgmt", Value::from("wpa-psk"));
+                settings.insert("psk", Value::from(passphrase));
+            }
+            Security::Wpa3 => {
+                settings.insert("key-mgmt", Value::from("sae"));
+                settings.insert("psk", Value::from(passphrase));
+            }
+        }
+
+        settings
+    }
+}
+
+/// `NMWepKeyType`: 1 for a key typed in as is, 5 or 13 characters or 10 or 26
+/// hex digits, otherwise 2 for a passphrase NetworkManager hashes into one.
+fn wep_key_type(passphrase: &str) -> u32 {
+    let is_hex = passphrase.chars().all(|c| c.is_ascii_hexdigit());
+
+    match passphrase.len() {
+        5 | 13 if passphrase.is_ascii() => 1,
+        10 | 26 if is_hex => 1,
+        _ => 2,
+    }
+}
+
+impl std::fmt::Display for Security {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Security::Open => write!(f, "Open"),
+            Security::Wep => write!(f, "WEP"),
+            Security::Wpa => write!(f, "WPA"),
+            Security::Wpa3 => write!(f, "WPA3"),
+            Security::Enterprise => write!(f, "802.1X"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    pub strength: u8,
+    pub security: Security,
+    /// Strongest access point broadcasting the SSID.
+    pub access_point: OwnedObjectPath,
+    /// Saved connection profile, if we've been on this network before.
+    pub connection: Option<OwnedObjectPath>,
+}
+
+impl WifiNetwork {
+    /// Whether connecting needs a passphrase we don't have yet.
+    pub fn needs_passphrase(&self) -> bool {
+        self.connection.is_none() && !matches!(self.security, Security::Open)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VpnState {
+    Disconnected,
+    Activating,
+    Connected,
+    Deactivating,
+    /// Why the last attempt went down, until the next one.
+    Failed(String),
+}
+
+/// A VPN or WireGuard profile.
+#[derive(Debug, Clone)]
+pub struct VpnConnection {
+    pub name: String,
+    /// `WireGuard`, `OpenVPN`, …
+    pub kind: String,
+    pub profile: OwnedObjectPath,
+    pub active: Option<OwnedObjectPath>,
+    pub state: VpnState,
+}
+
+/// Human readable `NMActiveConnectionStateReason`, for the ones that are failures.
+fn failure_reason(reason: u32) -> Option<&'static str> {
+    let reason = match reason {
+        3 => "The device disconnected",
+        4 => "The VPN service stopped",
+        5 => "The IP configuration is invalid",
+        6 => "The connection timed out",
+        7 => "The VPN service took too long to start",
+        8 => "The VPN service failed to start",
+        9 => "No secrets were provided",
+        10 => "Login failed",
+        11 => "The connection was removed",
+        12 => "A connection it depends on failed",
+        13 => "The device couldn't be set up",
+        14 => "The device was removed",
+        _ => return None,
+    };
+
+    Some(reason)
+}
+
+/// What the event stream has to remember between snapshots: an active
+/// connection is gone by the time we'd look at why it went down.
+#[derive(Debug, Default)]
+struct ConnectionTracker {
+    /// Active connection object path to the UUID of the profile it was
+    /// activated from.
+    active: HashMap<String, String>,
+    /// Last failure of each profile, by UUID.
+    failures: HashMap<String, String>,
+}
+
+impl ConnectionTracker {
+    /// Looks for `StateChanged` signals of active connections going down.
+    fn record(&mut self, message: &zbus::Message) {
+        let header = message.header();
+        if header.member().map(|member| member.as_str()) != Some("StateChanged") {
+            return;
+        }
+        let Some(uuid) = header
+            .path()
+            .and_then(|path| self.active.get(path.as_str()))
+        else {
+            return;
+        };
+        let Ok((state, reason)) = message.body().deserialize::<(u32, u32)>() else {
+            return;
+        };
+
+        if state == ACTIVE_STATE_DEACTIVATED {
+            if let Some(reason) = failure_reason(reason) {
+                self.failures.insert(uuid.clone(), reason.to_owned());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WiredInfo {
+    pub interface: String,
+    /// Whether a cable is plugged in with a link partner on the other end.
+    pub carrier: bool,
+    /// Mb/s, 0 when unknown.
+    pub speed: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModemInfo {
+    pub operator: String,
+    /// Percentage, from 0 to 100.
+    pub signal: u32,
+}
+
+/// What the hotspot is called and how to join it. Saved as `hotspot.json` in
+/// the config directory, without the password: NetworkManager keeps that in
+/// the profile already.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotspotConfig {
+    pub ssid: String,
+    /// Empty to keep the one the profile has.
+    #[serde(skip)]
+    pub password: String,
+}
+
+impl Default for HotspotConfig {
+    fn default() -> Self {
+        Self {
+            ssid: "wawi".to_owned(),
+            password: String::new(),
+        }
+    }
+}
+
+impl HotspotConfig {
+    /// Whether NetworkManager will take it: an SSID is at most 32 bytes and a
+    /// WPA passphrase 8 to 63 characters.
+    pub fn is_valid(&self) -> bool {
+        (1..=32).contains(&self.ssid.len())
+            && (self.password.is_empty() || (8..=63).contains(&self.password.chars().count()))
+    }
+
+    fn settings(
+        &self,
+        uuid: Option<String>,
+    ) -> HashMap<&'static str, HashMap<&'static str, Value<'_>>> {
+        let mut connection = HashMap::from([
+            ("id", Value::from(HOTSPOT_ID)),
+            ("type", Value::from("802-11-wireless")),
+            ("autoconnect", Value::from(false)),
+        ]);
+        if let Some(uuid) = uuid {
+            connection.insert("uuid", Value::from(uuid));
+        }
+
+        // Without a passphrase, NetworkManager keeps the one it has.
+        let mut security = HashMap::from([("key-mgmt", Value::from("wpa-psk"))]);
+        if !self.password.is_empty() {
+            security.insert("psk", Value::from(self.password.as_str()));
+        }
+
+        HashMap::from([
+            ("connection", connection),
+            (
+                "802-11-wireless",
+                HashMap::from([
+                    ("ssid", Value::from(self.ssid.as_bytes())),
+                    ("mode", Value::from("ap")),
+                ]),
+            ),
+            ("802-11-wireless-security", security),
+            // NetworkManager runs a DHCP server and NATs the clients.
+            ("ipv4", HashMap::from([("method", Value::from("shared"))])),
+            ("ipv6", HashMap::from([("method", Value::from("ignore"))])),
+        ])
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HotspotInfo {
+    pub ssid: String,
+    pub interface: String,
+    pub active: OwnedObjectPath,
+}
+
+/// Only ever sent by value, once per update, so its size doesn't matter.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum NetworkInfo {
+    NotAvailable,
+    Available {
+        is_wifi_available: bool,
+        wireless_enabled: bool,
+        /// Access point the Wi-Fi device is currently associated to.
+        wifi: Option<WifiNetwork>,
+        /// Scanned networks, one per SSID, strongest first.
+        networks: Vec<WifiNetwork>,
+        vpns: Vec<VpnConnection>,
+        wired: Option<WiredInfo>,
+        modem: Option<ModemInfo>,
+        hotspot: Option<HotspotInfo>,
+    },
+}
+
+async fn connection() -> zbus::Result<NetworkManagerProxy<'static>> {
+    let connection = zbus::Connection::system().await?;
+    let network_manager = NetworkManagerProxy::new(&connection).await?;
+
+    Ok(network_manager)
+}
+
+/// Proxies are built without caching, since every snapshot is taken from scratch.
+async fn device_path(
+    network_manager: &NetworkManagerProxy<'_>,
+    device_type: u32,
+) -> zbus::Result<Option<OwnedObjectPath>> {
+    let conn = network_manager.inner().connection();
+
+    for path in network_manager.get_devices().await? {
+        let device = DeviceProxy::builder(conn)
+            .path(path.clone())?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?;
+
+        if device.device_type().await? == device_type {
+            return Ok(Some(path));
+        }
+    }
+
+    Ok(None)
+}
+
+async fn access_point(conn: &zbus::Connection, path: OwnedObjectPath) -> zbus::Result<WifiNetwork> {
+    let access_point = AccessPointProxy::builder(conn)
+        .path(path.clone())?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+
+    Ok(WifiNetwork {
+        ssid: String::from_utf8_lossy(&access_point.ssid().await?).into_owned(),
+        strength: access_point.strength().await?,
+        security: Security::from_flags(
+            access_point.flags().await?,
+            access_point.wpa_flags().await?,
+            access_point.rsn_flags().await?,
+        ),
+        access_point: path,
+        connection: None,
+    })
+}
+
+fn setting<'a>(
+    settings: &'a HashMap<String, HashMap<String, OwnedValue>>,
+    group: &str,
+    key: &str,
+) -> Option<&'a OwnedValue> {
+    settings.get(group)?.get(key)
+}
+
+type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+fn string_setting(settings: &ConnectionSettings, group: &str, key: &str) -> Option<String> {
+    setting(settings, group, key)?.downcast_ref::<String>().ok()
+}
+
+fn ssid_setting(settings: &ConnectionSettings) -> Option<String> {
+    let ssid = setting(settings, "802-11-wireless", "ssid")?
+        .downcast_ref::<&Array>()
+        .ok()?;
+    let ssid: Vec<u8> = ssid
+        .inner()
+        .iter()
+        .filter_map(|byte| byte.downcast_ref::<u8>().ok())
+        .collect();
+
+    Some(String::from_utf8_lossy(&ssid).into_owned())
+}
+
+/// Hotspots are Wi-Fi profiles too, in access point mode.
+fn is_hotspot(settings: &ConnectionSettings) -> bool {
+    string_setting(settings, "802-11-wireless", "mode").as_deref() == Some("ap")
+}
+
+/// Every saved connection profile, along with its settings.
+async fn saved_profiles(
+    conn: &zbus::Connection,
+) -> zbus::Result<Vec<(OwnedObjectPath, ConnectionSettings)>> {
+    let settings = SettingsProxy::new(conn).await?;
+    let mut profiles = Vec::new();
+
+    for path in settings.list_connections().await? {
+        let connection = SettingsConnectionProxy::builder(conn)
+            .path(path.clone())?
+            .build()
+            .await?;
+
+        profiles.push((path, connection.get_settings().await?));
+    }
+
+    Ok(profiles)
+}
+
+/// Saved Wi-Fi connection profiles, by SSID.
+fn saved_networks(
+    profiles: &[(OwnedObjectPath, ConnectionSettings)],
+) -> HashMap<String, OwnedObjectPath> {
+    let mut networks = HashMap::new();
+
+    for (path, settings) in profiles {
+        // Not something to join.
+        if is_hotspot(settings) {
+            continue;
+        }
+
+        if let Some(ssid) = ssid_setting(settings) {
+            networks.insert(ssid, path.clone());
+        }
+    }
+
+    networks
+}
+
+/// The network we're on, and every other one in range.
+async fn wifi_networks(
+    conn: &zbus::Connection,
+    wifi_device: OwnedObjectPath,
+    saved: &HashMap<String, OwnedObjectPath>,
+) -> zbus::Result<(Option<WifiNetwork>, Vec<WifiNetwork>)> {
+    let wireless = WirelessProxy::builder(conn)
+        .path(wifi_device)?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+    let active_access_point = wireless.active_access_point().await?;
+
+    let mut networks: Vec<WifiNetwork> = Vec::new();
+    let mut wifi = None;
+
+    for path in wireless.get_all_access_points().await? {
+        // Access points come and go while we're reading them.
+        let Ok(mut network) = access_point(conn, path).await else {
+            continue;
+        };
+        network.connection = saved.get(&network.ssid).cloned();
+
+        if network.access_point == active_access_point {
+            wifi = Some(network.clone());
+        }
+
+        // Hidden networks don't broadcast an SSID to pick them by.
+        if network.ssid.is_empty() {
+            continue;
+        }
+
+        match networks.iter_mut().find(|known| known.ssid == network.ssid) {
+            Some(known) if known.strength < network.strength => *known = network,
+            Some(_) => {}
+            None => networks.push(network),
+        }
+    }
+
+    networks.sort_by_key(|network| std::cmp::Reverse(network.strength));
+
+    Ok((wifi, networks))
+}
+
+/// `WireGuard` for WireGuard profiles, the plugin name for the others.
+fn vpn_kind(settings: &ConnectionSettings) -> Option<String> {
+    match string_setting(settings, "connection", "type")?.as_str() {
+        "wireguard" => Some("WireGuard".to_owned()),
+        "vpn" => {
+            let service = string_setting(settings, "vpn", "service-type").unwrap_or_default();
+            let kind = match service.rsplit('.').next().unwrap_or_default() {
+                "openvpn" => "OpenVPN".to_owned(),
+                "" => "VPN".to_owned(),
+                plugin => capitalize(plugin),
+            };
+
+            Some(kind)
+        }
+        _ => None,
+    }
+}
+
+/// `openconnect` → `Openconnect`, good enough for plugins we don't know about.
+fn capitalize(plugin: &str) -> String {
+    let mut chars = plugin.chars();
+
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Profile to its active connection and state.
+type ActiveConnections = HashMap<OwnedObjectPath, (OwnedObjectPath, u32)>;
+
+async fn active_connections(
+    network_manager: &NetworkManagerProxy<'_>,
+    tracker: &mut ConnectionTracker,
+) -> zbus::Result<ActiveConnections> {
+    let conn = network_manager.inner().connection();
+
+    let mut active = HashMap::new();
+    tracker.active.clear();
+
+    for path in network_manager.active_connections().await? {
+        let active_connection = ActiveConnectionProxy::builder(conn)
+            .path(path.clone())?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?;
+        let (Ok(profile), Ok(state), Ok(uuid)) = (
+            active_connection.connection().await,
+            active_connection.state().await,
+            active_connection.uuid().await,
+        ) else {
+            continue;
+        };
+
+        tracker.active.insert(path.to_string(), uuid);
+        active.insert(profile, (path, state));
+    }
+
+    Ok(active)
+}
+
+fn vpn_connections(
+    profiles: &[(OwnedObjectPath, ConnectionSettings)],
+    active: &ActiveConnections,
+    tracker: &mut ConnectionTracker,
+) -> Vec<VpnConnection> {
+    let mut vpns = Vec::new();
+
+    for (profile, settings) in profiles {
+        let Some(kind) = vpn_kind(settings) else {
+            continue;
+        };
+
+        let uuid = string_setting(settings, "connection", "uuid").unwrap_or_default();
+        let (active, state) = match active.get(profile) {
+            Some((path, state)) => (Some(path.clone()), *state),
+            None => (None, ACTIVE_STATE_DEACTIVATED),
+        };
+
+        // A new attempt, so the last failure no longer applies.
+        if matches!(state, ACTIVE_STATE_ACTIVATING | ACTIVE_STATE_ACTIVATED) {
+            tracker.failures.remove(&uuid);
+        }
+
+        let state = match state {
+            ACTIVE_STATE_ACTIVATING => VpnState::Activating,
+            ACTIVE_STATE_ACTIVATED => VpnState::Connected,
+            ACTIVE_STATE_DEACTIVATING => VpnState::Deactivating,
+            // Failed connections linger for a bit before going away.
+            _ => match tracker.failures.get(&uuid) {
+                Some(reason) => VpnState::Failed(reason.clone()),
+                None => VpnState::Disconnected,
+            },
+        };
+
+        vpns.push(VpnConnection {
+            name: string_setting(settings, "connection", "id").unwrap_or_default(),
+            kind,
+            profile: profile.clone(),
+            active,
+            state,
+        });
+    }
+
+    vpns.sort_by(|a, b| a.name.cmp(&b.name));
+
+    vpns
+}
+
+/// Our hotspot, or any other access point mode profile, while it's up.
+async fn hotspot_info(
+    conn: &zbus::Connection,
+    profiles: &[(OwnedObjectPath, ConnectionSettings)],
+    active: &ActiveConnections,
+    wifi_device: &OwnedObjectPath,
+) -> zbus::Result<Option<HotspotInfo>> {
+    let hotspot = profiles.iter().find_map(|(profile, settings)| {
+        let (path, state) = active.get(profile)?;
+        let is_up = matches!(*state, ACTIVE_STATE_ACTIVATING | ACTIVE_STATE_ACTIVATED);
+
+        (is_hotspot(settings) && is_up).then(|| (ssid_setting(settings), path.clone()))
+    });
+    let Some((ssid, active)) = hotspot else {
+        return Ok(None);
+    };
+
+    let device = DeviceProxy::builder(conn)
+        .path(wifi_device.clone())?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+
+    Ok(Some(HotspotInfo {
+        ssid: ssid.unwrap_or_default(),
+        interface: device.interface().await?,
+        active,
+    }))
+}
+
+async fn wired_info(
+    conn: &zbus::Connection,
+    wired_device: OwnedObjectPath,
+) -> zbus::Result<WiredInfo> {
+    let device = DeviceProxy::builder(conn)
+        .path(wired_device.clone())?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+    let wired = WiredProxy::builder(conn)
+        .path(wired_device)?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+
+    Ok(WiredInfo {
+        interface: device.interface().await?,
+        carrier: wired.carrier().await?,
+        speed: wired.speed().await?,
+    })
+}
+
+/// First modem ModemManager knows about. Most machines have no modem, or no
+/// ModemManager at all, so any error just means there's nothing to show.
+async fn modem_info(conn: &zbus::Connection) -> Option<ModemInfo> {
+    let objects = zbus::fdo::ObjectManagerProxy::builder(conn)
+        .destination(MODEM_MANAGER)
+        .ok()?
+        .path("/org/freedesktop/ModemManager1")
+        .ok()?
+        .build()
+        .await
+        .ok()?
+        .get_managed_objects()
+        .await
+        .ok()?;
+
+    let (_, interfaces) = objects
+        .iter()
+        .filter(|(_, interfaces)| interfaces.contains_key(MODEM_INTERFACE))
+        .min_by_key(|(path, _)| path.as_str())?;
+
+    // `(quality, recent)`, the quality being a percentage.
+    let (signal, _) = interfaces
+        .get(MODEM_INTERFACE)?
+        .get("SignalQuality")
+        .and_then(|quality| quality.try_clone().ok())
+        .and_then(|quality| <(u32, bool)>::try_from(quality).ok())
+        .unwrap_or_default();
+    let operator = interfaces
+        .get(MODEM_3GPP_INTERFACE)
+        .and_then(|modem| modem.get("OperatorName"))
+        .and_then(|operator| operator.downcast_ref::<String>().ok())
+        .unwrap_or_default();
+
+    Some(ModemInfo { operator, signal })
+}
+
+/// `profiles` are passed in as they rarely change, and reading them all is
+/// what takes the longest.
+async fn network_info(
+    network_manager: &NetworkManagerProxy<'_>,
+    profiles: &[(OwnedObjectPath, ConnectionSettings)],
+    tracker: &mut ConnectionTracker,
+) -> zbus::Result<NetworkInfo> {
+    let conn = network_manager.inner().connection();
+    let active = active_connections(network_manager, tracker).await?;
+
+    let wifi_device = device_path(network_manager, DEVICE_TYPE_WIFI).await?;
+    let is_wifi_available = wifi_device.is_some();
+    let (wifi, networks, hotspot) = match wifi_device {
+        Some(wifi_device) => {
+            let hotspot = hotspot_info(conn, profiles, &active, &wifi_device).await?;
+            let (wifi, networks) =
+                wifi_networks(conn, wifi_device, &saved_networks(profiles)).await?;
+
+            (wifi, networks, hotspot)
+        }
+        None => (None, Vec::new(), None),
+    };
+
+    Ok(NetworkInfo::Available {
+        is_wifi_available,
+        wireless_enabled: network_manager.wireless_enabled().await?,
+        wifi,
+        networks,
+        vpns: vpn_connections(profiles, &active, tracker),
+        wired: match device_path(network_manager, DEVICE_TYPE_ETHERNET).await? {
+            Some(wired_device) => Some(wired_info(conn, wired_device).await?),
+            None => None,
+        },
+        modem: modem_info(conn).await,
+        hotspot,
+    })
+}
+
+/// Asks the Wi-Fi device to scan; results show up through the event stream.
+pub async fn request_scan() {
+    let result = async {
+        let network_manager = connection().await?;
+        let conn = network_manager.inner().connection();
+
+        let Some(wifi_device) = device_path(&network_manager, DEVICE_TYPE_WIFI).await? else {
+            return Ok(());
+        };
+
+        WirelessProxy::builder(conn)
+            .path(wifi_device)?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?
+            .request_scan(HashMap::new())
+            .await
+    }
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Couldn't scan for Wi-Fi networks: {err}");
+    }
+}
+
+/// Joins `network`, reusing its saved profile if there's one, otherwise
+/// creating a new one with `passphrase`.
+pub async fn activate(
+    conn: &zbus::Connection,
+    network: &WifiNetwork,
+    passphrase: &str,
+) -> zbus::Result<()> {
+    let network_manager = NetworkManagerProxy::new(conn).await?;
+
+    let Some(wifi_device) = device_path(&network_manager, DEVICE_TYPE_WIFI).await? else {
+        return Err(zbus::Error::Failure("No Wi-Fi device".to_owned()));
+    };
+
+    if let Some(connection) = &network.connection {
+        network_manager
+            .activate_connection(connection, &wifi_device, &network.access_point)
+            .await?;
+        return Ok(());
+    }
+
+    let mut settings = HashMap::new();
+    let security = network.security.settings(passphrase);
+    if !security.is_empty() {
+        settings.insert("802-11-wireless-security", security);
+    }
+
+    network_manager
+        .add_and_activate_connection(settings, &wifi_device, &network.access_point)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn connect(network: WifiNetwork, passphrase: String) -> Result<(), String> {
+    let result = async {
+        let conn = zbus::Connection::system().await?;
+        activate(&conn, &network, &passphrase).await
+    }
+    .await;
+
+    result.map_err(|err| {
+        eprintln!("Couldn't connect to {}: {err}", network.ssid);
+        err.to_string()
+    })
+}
+
+pub async fn set_wireless_enabled(enabled: bool) -> bool {
+    let result = async { connection().await?.set_wireless_enabled(enabled).await }.await;
+
+    if let Err(err) = result {
+        eprintln!("Couldn't toggle the Wi-Fi: {err}");
+    }
+
+    enabled
+}
+
+/// Brings a VPN up from its profile, or takes it down.
+pub async fn set_vpn_active(vpn: VpnConnection, active: bool) -> Result<(), String> {
+    let result = async {
+        let network_manager = connection().await?;
+        let none = ObjectPath::try_from("/")?;
+
+        let is_up = matches!(vpn.state, VpnState::Activating | VpnState::Connected);
+
+        match (&vpn.active, active) {
+            // Connections going or gone down linger for a while, but they
+            // can't be brought back up, only the profile can.
+            (_, true) if !is_up => network_manager
+                .activate_connection(&vpn.profile, &none, &none)
+                .await
+                .map(|_| ()),
+            (Some(active_connection), false) if is_up => {
+                network_manager
+                    .deactivate_connection(active_connection)
+                    .await
+            }
+            // Already where it's asked to be.
+            _ => Ok(()),
+        }
+    }
+    .await;
+
+    result.map_err(|err| {
+        eprintln!("Couldn't toggle {}: {err}", vpn.name);
+        err.to_string()
+    })
+}
+
+/// Shares our connection over Wi-Fi, updating the hotspot profile from
+/// `config` or creating it the first time.
+pub async fn start_hotspot(config: HotspotConfig) -> Result<(), String> {
+    let result = async {
+        let network_manager = connection().await?;
+        let conn = network_manager.inner().connection();
+        let none = ObjectPath::try_from("/")?;
+
+        let Some(wifi_device) = device_path(&network_manager, DEVICE_TYPE_WIFI).await? else {
+            return Err(zbus::Error::Failure("No Wi-Fi device".to_owned()));
+        };
+
+        let profile = saved_profiles(conn)
+            .await?
+            .into_iter()
+            .find(|(_, settings)| {
+                is_hotspot(settings)
+                    && string_setting(settings, "connection", "id").as_deref() == Some(HOTSPOT_ID)
+            });
+
+        match profile {
+            Some((path, settings)) => {
+                // The same UUID, or NetworkManager would see a different profile.
+                let uuid = string_setting(&settings, "connection", "uuid");
+                SettingsConnectionProxy::builder(conn)
+                    .path(path.clone())?
+                    .build()
+                    .await?
+                    .update(config.settings(uuid))
+                    .await?;
+
+                network_manager
+                    .activate_connection(&path, &wifi_device, &none)
+                    .await?;
+            }
+            None if config.password.is_empty() => {
+                return Err(zbus::Error::Failure(
+                    "The hotspot needs a password".to_owned(),
+                ));
+            }
+            None => {
+                network_manager
+                    .add_and_activate_connection(config.settings(None), &wifi_device, &none)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+    .await;
+
+    result.map_err(|err| {
+        eprintln!("Couldn't start the hotspot: {err}");
+        err.to_string()
+    })
+}
+
+/// Takes the hotspot down, then gets back on `previous`, the Wi-Fi profile we
+/// were using before. Without one, NetworkManager picks a network by itself.
+pub async fn stop_hotspot(
+    hotspot: HotspotInfo,
+    previous: Option<OwnedObjectPath>,
+) -> Result<(), String> {
+    let result = async {
+        let network_manager = connection().await?;
+        network_manager
+            .deactivate_connection(&hotspot.active)
+            .await?;
+
+        let (Some(previous), Some(wifi_device)) = (
+            previous,
+            device_path(&network_manager, DEVICE_TYPE_WIFI).await?,
+        ) else {
+            return Ok(());
+        };
+        let none = ObjectPath::try_from("/")?;
+
+        network_manager
+            .activate_connection(&previous, &wifi_device, &none)
+            .await
+            .map(|_| ())
+    }
+    .await;
+
+    result.map_err(|err| {
+        eprintln!("Couldn't stop the hotspot: {err}");
+        err.to_string()
+    })
+}
+
+/// Clients on the hotspot, going by the neighbours the kernel has resolved on
+/// `interface`. NetworkManager doesn't keep track of them, so this is the
+/// best there is, and `None` if even that can't be read.
+pub async fn hotspot_clients(interface: String) -> Option<usize> {
+    let arp = match tokio::fs::read_to_string("/proc/net/arp").await {
+        Ok(arp) => arp,
+        Err(err) => {
+            eprintln!("Couldn't read /proc/net/arp: {err}");
+            return None;
+        }
+    };
+
+    Some(count_clients(&arp, &interface))
+}
+
+/// `IP address  HW type  Flags  HW address  Mask  Device`, a header line then
+/// one entry per neighbour; flag `0x2` marks the complete ones.
+fn count_clients(arp: &str, interface: &str) -> usize {
+    arp.lines()
+        .skip(1)
+        .filter(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            fields.get(2) == Some(&"0x2") && fields.get(5) == Some(&interface)
+        })
+        .count()
+}
+
+/// How long to wait for the rest of a burst of signals, e.g. during a scan,
+/// before taking a single snapshot for all of them.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Interfaces whose properties end up in a snapshot, along with the settings
+/// ones. The others, e.g. the IP configuration and the traffic statistics,
+/// change all the time for nothing.
+const WATCHED_INTERFACES: [&str; 6] = [
+    "org.freedesktop.NetworkManager",
+    "org.freedesktop.NetworkManager.Device",
+    "org.freedesktop.NetworkManager.Device.Wired",
+    "org.freedesktop.NetworkManager.Device.Wireless",
+    "org.freedesktop.NetworkManager.AccessPoint",
+    "org.freedesktop.NetworkManager.Connection.Active",
+];
+const SETTINGS_INTERFACE: &str = "org.freedesktop.NetworkManager.Settings";
+
+/// What a batch of signals calls for.
+#[derive(Debug, Default)]
+struct Changes {
+    snapshot: bool,
+    /// Whether saved profiles were added, removed or edited.
+    profiles: bool,
+}
+
+impl Changes {
+    fn record(&mut self, message: &zbus::Message) {
+        let header = message.header();
+        let interface = header.interface().map(|interface| interface.as_str());
+
+        let interface = match interface {
+            Some("org.freedesktop.DBus.Properties") => {
+                let body = message.body();
+                match body.deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>() {
+                    Ok((interface, _, _)) => interface,
+                    Err(_) => return,
+                }
+            }
+            Some(interface) => interface.to_owned(),
+            None => return,
+        };
+
+        if header.sender().map(|sender| sender.as_str()) != Some(MODEM_MANAGER)
+            && !interface.starts_with(SETTINGS_INTERFACE)
+            && !WATCHED_INTERFACES.contains(&interface.as_str())
+        {
+            return;
+        }
+
+        self.snapshot = true;
+        self.profiles |= interface.starts_with(SETTINGS_INTERFACE);
+    }
+}
+
+/// Follows NetworkManager on `conn`. Signals are read from `signal_conn`,
+/// since a backed up queue stalls the connection reading it. Both are
+/// parameters so a private bus can stand in for the system one.
+pub async fn event_stream(
+    conn: zbus::Connection,
+    signal_conn: zbus::Connection,
+) -> zbus::Result<impl futures::Stream<Item = NetworkInfo>> {
+    let network_manager = NetworkManagerProxy::builder(&conn)
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+
+    // Includes the strength of the current access point. What matters is
+    // sorted out by `Changes`.
+    let properties_changed = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.freedesktop.NetworkManager")?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .build();
+    // Only this one carries the reason an active connection went down.
+    let state_changed = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.freedesktop.NetworkManager")?
+        .interface("org.freedesktop.NetworkManager.Connection.Active")?
+        .member("StateChanged")?
+        .build();
+    // Profiles being added or removed.
+    let settings = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.freedesktop.NetworkManager")?
+        .interface(SETTINGS_INTERFACE)?
+        .build();
+    // Profiles being edited.
+    let settings_connection = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender("org.freedesktop.NetworkManager")?
+        .interface("org.freedesktop.NetworkManager.Settings.Connection")?
+        .build();
+    // Modems coming and going as well as their signal changing.
+    let modem_manager = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(MODEM_MANAGER)?
+        .build();
+    let signals = futures::stream_select!(
+        zbus::MessageStream::for_match_rule(properties_changed, &signal_conn, None).await?,
+        zbus::MessageStream::for_match_rule(state_changed, &signal_conn, None).await?,
+        zbus::MessageStream::for_match_rule(settings, &signal_conn, None).await?,
+        zbus::MessageStream::for_match_rule(settings_connection, &signal_conn, None).await?,
+        zbus::MessageStream::for_match_rule(modem_manager, &signal_conn, None).await?,
+    );
+
+    let mut tracker = ConnectionTracker::default();
+    let profiles = saved_profiles(&conn).await?;
+    let initial = network_info(&network_manager, &profiles, &mut tracker).await?;
+
+    let updates = futures::stream::unfold(
+        (signals.ready_chunks(64), network_manager, profiles, tracker),
+        |(mut signals, network_manager, mut profiles, mut tracker)| async move {
+            loop {
+                let mut messages = signals.next().await?;
+                tokio::time::sleep(DEBOUNCE).await;
+                while let Some(Some(more)) = signals.next().now_or_never() {
+                    messages.extend(more);
+                }
+
+                let mut changes = Changes::default();
+                for message in messages.into_iter().flatten() {
+                    tracker.record(&message);
+                    changes.record(&message);
+                }
+                if !changes.snapshot {
+                    continue;
+                }
+
+                let conn = network_manager.inner().connection();
+                let info = async {
+                    if changes.profiles {
+                        profiles = saved_profiles(conn).await?;
+                    }
+                    network_info(&network_manager, &profiles, &mut tracker).await
+                };
+
+                // Things come and go while we're reading them, so a failed
+                // snapshot is most likely fixed by the next signal.
+                match info.await {
+                    Ok(info) => {
+                        return Some((info, (signals, network_manager, profiles, tracker)));
+                    }
+                    Err(err) => eprintln!("Couldn't read the network state: {err}"),
+                }
+            }
+        },
+    );
+
+    Ok(futures::stream::once(async { initial }).chain(updates))
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<NetworkInfo>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("networkmanager", id),
+        async move {
+            let stream = async {
+                let conn = zbus::Connection::system().await?;
+                let signal_conn = zbus::Connection::system().await?;
+                event_stream(conn, signal_conn).await
+            };
+
+            match stream.await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("An error has ocurred: {err}");
+                    futures::future::pending().await
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::testing::{self, PrivateBus};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn security_is_read_from_the_flags() {
+        assert_eq!(Security::from_flags(0, 0, 0), Security::Open);
+        assert_eq!(Security::from_flags(AP_FLAGS_PRIVACY, 0, 0), Security::Wep);
+        assert_eq!(
+            Security::from_flags(AP_FLAGS_PRIVACY, 0, AP_SEC_KEY_MGMT_PSK),
+            Security::Wpa
+        );
+        assert_eq!(
+            Security::from_flags(AP_FLAGS_PRIVACY, 0, AP_SEC_KEY_MGMT_SAE),
+            Security::Wpa3
+        );
+        assert_eq!(
+            Security::from_flags(AP_FLAGS_PRIVACY, AP_SEC_KEY_MGMT_802_1X, 0),
+            Security::Enterprise
+        );
+    }
+
+    #[test]
+    fn wep_keys_are_told_from_passphrases() {
+        assert_eq!(wep_key_type("abcde"), 1);
+        assert_eq!(wep_key_type("0123456789"), 1);
+        assert_eq!(wep_key_type("0123456789abcdef0123456789"), 1);
+        assert_eq!(wep_key_type("correct horse"), 1);
+        assert_eq!(wep_key_type("correct horse battery"), 2);
+        assert_eq!(wep_key_type("xyz0123456"), 2);
+    }
+
+    #[test]
+    fn clients_are_counted_per_interface() {
+        let arp = "IP address       HW type     Flags       HW address            Mask     Device
+10.42.0.12       0x1         0x2         aa:bb:cc:dd:ee:01     *        wlan0
+10.42.0.13       0x1         0x0         00:00:00:00:00:00     *        wlan0
+192.168.1.1      0x1         0x2         aa:bb:cc:dd:ee:02     *        eth0
+";
+
+        assert_eq!(count_clients(arp, "wlan0"), 1);
+        assert_eq!(count_clients(arp, "eth0"), 1);
+        assert_eq!(count_clients(arp, "wlan1"), 0);
+    }
+
+    #[test]
+    fn the_hotspot_password_stays_with_networkmanager() {
+        let config = HotspotConfig {
+            ssid: "wawi".to_owned(),
+            password: "hunter22".to_owned(),
+        };
+        assert!(!serde_json::to_string(&config).unwrap().contains("hunter22"));
+        assert!(config.settings(None)["802-11-wireless-security"].contains_key("psk"));
+
+        let config: HotspotConfig = serde_json::from_str(r#"{"ssid": "wawi"}"#).unwrap();
+        assert!(config.is_valid());
+        assert!(!config.settings(None)["802-11-wireless-security"].contains_key("psk"));
+    }
+
+    #[test]
+    fn vpn_failures_outlive_the_active_connection() {
+        let profile =
+            OwnedObjectPath::try_from("/org/freedesktop/NetworkManager/Settings/7").unwrap();
+        let active_path = "/org/freedesktop/NetworkManager/ActiveConnection/3";
+        let settings: ConnectionSettings = HashMap::from([(
+            "connection".to_string(),
+            HashMap::from([
+                ("id", Value::from("Work")),
+                ("type", Value::from("wireguard")),
+                ("uuid", Value::from("0b2e")),
+            ])
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.try_to_owned().unwrap()))
+            .collect(),
+        )]);
+        let profiles = [(profile.clone(), settings)];
+
+        let mut tracker = ConnectionTracker::default();
+        tracker
+            .active
+            .insert(active_path.to_string(), "0b2e".to_string());
+        let state_changed = zbus::Message::signal(
+            active_path,
+            "org.freedesktop.NetworkManager.Connection.Active",
+            "StateChanged",
+        )
+        .unwrap()
+        .build(&(ACTIVE_STATE_DEACTIVATED, 10u32))
+        .unwrap();
+        tracker.record(&state_changed);
+
+        // Still listed, on its way out.
+        let active_connection = OwnedObjectPath::try_from(active_path).unwrap();
+        let active = HashMap::from([(
+            profile.clone(),
+            (active_connection.clone(), ACTIVE_STATE_DEACTIVATED),
+        )]);
+        let vpns = vpn_connections(&profiles, &active, &mut tracker);
+        assert_eq!(vpns[0].state, VpnState::Failed("Login failed".to_owned()));
+
+        // Gone for good.
+        let vpns = vpn_connections(&profiles, &HashMap::new(), &mut tracker);
+        assert_eq!(vpns[0].state, VpnState::Failed("Login failed".to_owned()));
+
+        // Trying again.
+        let active = HashMap::from([(profile, (active_connection, ACTIVE_STATE_ACTIVATING))]);
+        let vpns = vpn_connections(&profiles, &active, &mut tracker);
+        assert_eq!(vpns[0].state, VpnState::Activating);
+        assert!(tracker.failures.is_empty());
+    }
+
+    const DEVICE_PATH: &str = "/org/freedesktop/NetworkManager/Devices/1";
+
+    type AddedConnection = HashMap<String, HashMap<String, OwnedValue>>;
+
+    struct MockNetworkManager {
+        wireless_enabled: bool,
+        added: Arc<Mutex<Option<AddedConnection>>>,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager")]
+    impl MockNetworkManager {
+        fn get_devices(&self) -> Vec<OwnedObjectPath> {
+            vec![OwnedObjectPath::try_from(DEVICE_PATH).unwrap()]
+        }
+
+        fn add_and_activate_connection(
+            &self,
+            connection: AddedConnection,
+            _device: ObjectPath<'_>,
+            _specific_object: ObjectPath<'_>,
+        ) -> (OwnedObjectPath, OwnedObjectPath) {
+            *self.added.lock().unwrap() = Some(connection);
+
+            (
+                OwnedObjectPath::try_from("/org/freedesktop/NetworkManager/Settings/1").unwrap(),
+                OwnedObjectPath::try_from("/org/freedesktop/NetworkManager/ActiveConnection/1")
+                    .unwrap(),
+            )
+        }
+
+        #[zbus(property)]
+        fn wireless_enabled(&self) -> bool {
+            self.wireless_enabled
+        }
+
+        #[zbus(property)]
+        fn active_connections(&self) -> Vec<OwnedObjectPath> {
+            Vec::new()
+        }
+    }
+
+    struct MockSettings;
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Settings")]
+    impl MockSettings {
+        fn list_connections(&self) -> Vec<OwnedObjectPath> {
+            Vec::new()
+        }
+    }
+
+    struct MockDevice;
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Device")]
+    impl MockDevice {
+        #[zbus(property)]
+        fn device_type(&self) -> u32 {
+            DEVICE_TYPE_WIFI
+        }
+
+        #[zbus(property)]
+        fn interface(&self) -> &str {
+            "wlan0"
+        }
+    }
+
+    struct MockWireless;
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+    impl MockWireless {
+        fn get_all_access_points(&self) -> Vec<OwnedObjectPath> {
+            (1..=3)
+                .map(|index| {
+                    OwnedObjectPath::try_from(format!(
+                        "/org/freedesktop/NetworkManager/AccessPoint/{index}"
+                    ))
+                    .unwrap()
+                })
+                .collect()
+        }
+
+        fn request_scan(&self, _options: HashMap<String, OwnedValue>) {}
+
+        #[zbus(property)]
+        fn active_access_point(&self) -> OwnedObjectPath {
+            OwnedObjectPath::try_from("/org/freedesktop/NetworkManager/AccessPoint/2").unwrap()
+        }
+    }
+
+    struct MockAccessPoint {
+        ssid: &'static str,
+        strength: u8,
+        flags: u32,
+        rsn_flags: u32,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+    impl MockAccessPoint {
+        #[zbus(property)]
+        fn ssid(&self) -> Vec<u8> {
+            self.ssid.as_bytes().to_vec()
+        }
+
+        #[zbus(property)]
+        fn strength(&self) -> u8 {
+            self.strength
+        }
+
+        #[zbus(property)]
+        fn flags(&self) -> u32 {
+            self.flags
+        }
+
+        #[zbus(property)]
+        fn wpa_flags(&self) -> u32 {
+            0
+        }
+
+        #[zbus(property)]
+        fn rsn_flags(&self) -> u32 {
+            self.rsn_flags
+        }
+    }
+
+    /// One Wi-Fi device that sees `home` twice and an old WEP network.
+    async fn start_network_manager(
+        bus: &PrivateBus,
+        added: Arc<Mutex<Option<AddedConnection>>>,
+    ) -> zbus::Connection {
+        let conn = bus.connect().await;
+
+        conn.object_server()
+            .at(
+                "/org/freedesktop/NetworkManager",
+                MockNetworkManager {
+                    wireless_enabled: true,
+                    added,
+                },
+            )
+            .await
+            .unwrap();
+        conn.object_server()
+            .at("/org/freedesktop/NetworkManager/Settings", MockSettings)
+            .await
+            .unwrap();
+        conn.object_server()
+            .at(DEVICE_PATH, MockDevice)
+            .await
+            .unwrap();
+        conn.object_server()
+            .at(DEVICE_PATH, MockWireless)
+            .await
+            .unwrap();
+
+        let access_points = [
+            ("home", 40, AP_FLAGS_PRIVACY, AP_SEC_KEY_MGMT_PSK),
+            ("home", 80, AP_FLAGS_PRIVACY, AP_SEC_KEY_MGMT_PSK),
+            ("legacy", 60, AP_FLAGS_PRIVACY, 0),
+        ];
+        for (index, (ssid, strength, flags, rsn_flags)) in access_points.into_iter().enumerate() {
+            conn.object_server()
+                .at(
+                    format!("/org/freedesktop/NetworkManager/AccessPoint/{}", index + 1),
+                    MockAccessPoint {
+                        ssid,
+                        strength,
+                        flags,
+                        rsn_flags,
+                    },
+                )
+                .await
+                .unwrap();
+        }
+
+        conn.request_name("org.freedesktop.NetworkManager")
+            .await
+            .unwrap();
+
+        conn
+    }
+
+    #[tokio::test]
+    async fn follows_the_wifi_state() {
+        let bus = PrivateBus::start();
+        let service = start_network_manager(&bus, Arc::default()).await;
+
+        let mut stream = Box::pin(
+            event_stream(bus.connect().await, bus.connect().await)
+                .await
+                .unwrap(),
+        );
+
+        let NetworkInfo::Available {
+            is_wifi_available,
+            wireless_enabled,
+            wifi,
+            networks,
+            ..
+        } = testing::next(&mut stream).await
+        else {
+            panic!("NetworkManager should be available");
+        };
+        assert!(is_wifi_available && wireless_enabled);
+        assert_eq!(wifi.map(|wifi| wifi.ssid).as_deref(), Some("home"));
+        let networks: Vec<_> = networks
+            .iter()
+            .map(|network| (network.ssid.as_str(), network.strength, network.security))
+            .collect();
+        assert_eq!(
+            networks,
+            [("home", 80, Security::Wpa), ("legacy", 60, Security::Wep)]
+        );
+
+        let network_manager = service
+            .object_server()
+            .interface::<_, MockNetworkManager>("/org/freedesktop/NetworkManager")
+            .await
+            .unwrap();
+        network_manager.get_mut().await.wireless_enabled = false;
+        network_manager
+            .get()
+            .await
+            .wireless_enabled_changed(network_manager.signal_context())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            testing::next(&mut stream).await,
+            NetworkInfo::Available {
+                wireless_enabled: false,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn joins_a_wep_network_with_its_key_type() {
+        let bus = PrivateBus::start();
+        let added = Arc::default();
+        let _service = start_network_manager(&bus, Arc::clone(&added)).await;
+
+        let network = WifiNetwork {
+            ssid: "legacy".to_owned(),
+            strength: 60,
+            security: Security::Wep,
+            access_point: OwnedObjectPath::try_from(
+                "/org/freedesktop/NetworkManager/AccessPoint/3",
+            )
+            .unwrap(),
+            connection: None,
+        };
+        activate(&bus.connect().await, &network, "abcde")
+            .await
+            .unwrap();
+
+        let added = added
+            .lock()
+            .unwrap()
+            .take()
+            .expect("a profile should be added");
+        let security = &added["802-11-wireless-security"];
+        assert_eq!(
+            security["key-mgmt"].downcast_ref::<String>().unwrap(),
+            "none"
+        );
+        assert_eq!(
+            security["wep-key0"].downcast_ref::<String>().unwrap(),
+            "abcde"
+        );
+        assert_eq!(security["wep-key-type"].downcast_ref::<u32>().unwrap(), 1);
+    }
+}
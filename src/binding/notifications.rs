@@ -0,0 +1,764 @@
+use iced::futures::{self, channel::mpsc, FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+use zbus::zvariant::{OwnedValue, Value};
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+const NOTIFICATIONS_PATH: &str = "/org/freedesktop/Notifications";
+
+/// How long a popup stays up when the sender leaves it to us.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// Whichever notification server is running, ours normally.
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+/// Why a notification went away, as sent in `NotificationClosed`. There's no
+/// `Expired`, as notifications stay in the history until dismissed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseReason {
+    Dismissed = 2,
+    /// The sender called `CloseNotification`.
+    Closed = 3,
+}
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    /// The sender's `.desktop` file name, without the extension, if it says.
+    pub desktop_entry: Option<String>,
+    pub summary: String,
+    pub body: String,
+    /// Action keys and their labels. `default` is the one for clicking the
+    /// notification itself.
+    pub actions: Vec<(String, String)>,
+    pub urgency: Urgency,
+    /// `None` if it stays up until dismissed.
+    pub timeout: Option<Duration>,
+    pub received: std::time::SystemTime,
+}
+
+/// A local time of day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeOfDay {
+    pub hour: u32,
+    pub minute: u32,
+}
+
+impl TimeOfDay {
+    fn minutes(self) -> u32 {
+        self.hour * 60 + self.minute
+    }
+
+    /// Right now, in the local time zone.
+    pub fn now() -> Self {
+        // SAFETY: `time` accepts a null pointer, and `tm` is plain old data
+        // which `localtime_r` fills in.
+        let tm = unsafe {
+            let now = libc::time(std::ptr::null_mut());
+            let mut tm: libc::tm = std::mem::zeroed();
+            libc::localtime_r(&now, &mut tm);
+            tm
+        };
+
+        Self {
+            hour: tm.tm_hour as u32,
+            minute: tm.tm_min as u32,
+        }
+    }
+}
+
+impl std::fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.hour, self.minute)
+    }
+}
+
+/// Quiet hours, going past midnight if `start` is later than `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+impl Schedule {
+    fn contains(&self, time: TimeOfDay) -> bool {
+        let (start, end, time) = (self.start.minutes(), self.end.minutes(), time.minutes());
+
+        if start <= end {
+            (start..end).contains(&time)
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+/// When popups are held back. Notifications still go to the history. Saved
+/// as `do-not-disturb.json` in the config directory, where the schedules and
+/// allowed applications are set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DoNotDisturb {
+    /// Turned on by hand, whatever the time.
+    pub enabled: bool,
+    pub schedules: Vec<Schedule>,
+    /// Whether critical notifications, e.g. a dying battery, pop up anyway.
+    pub allow_critical: bool,
+    /// Application names or desktop entries that pop up anyway.
+    pub allowed_apps: Vec<String>,
+}
+
+impl Default for DoNotDisturb {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            schedules: Vec::new(),
+            allow_critical: true,
+            allowed_apps: Vec::new(),
+        }
+    }
+}
+
+impl DoNotDisturb {
+    /// The schedule `time` falls in, if any.
+    pub fn active_schedule(&self, time: TimeOfDay) -> Option<&Schedule> {
+        self.schedules
+            .iter()
+            .find(|schedule| schedule.contains(time))
+    }
+
+    pub fn is_quiet(&self, time: TimeOfDay) -> bool {
+        self.enabled || self.active_schedule(time).is_some()
+    }
+
+    /// Whether `notification` gets a popup at `time`.
+    pub fn allows(&self, notification: &Notification, time: TimeOfDay) -> bool {
+        if !self.is_quiet(time) {
+            return true;
+        }
+        if self.allow_critical && notification.urgency == Urgency::Critical {
+            return true;
+        }
+
+        self.allowed_apps.iter().any(|app| {
+            app.eq_ignore_ascii_case(&notification.app_name)
+                || notification
+                    .desktop_entry
+                    .as_ref()
+                    .is_some_and(|entry| app.eq_ignore_ascii_case(entry))
+        })
+    }
+}
+
+/// The bus the server is on, for sending signals back to the senders.
+#[derive(Clone)]
+pub struct NotificationServer(zbus::Connection);
+
+impl NotificationServer {
+    /// Frees `id` for later notifications and tells the sender it's gone.
+    async fn closed(&self, id: u32, reason: CloseReason) -> zbus::Result<()> {
+        let server = self
+            .0
+            .object_server()
+            .interface::<_, Server>(NOTIFICATIONS_PATH)
+            .await?;
+        server.get_mut().await.open.remove(&id);
+
+        Server::notification_closed(server.signal_context(), id, reason as u32).await
+    }
+}
+
+impl std::fmt::Debug for NotificationServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationServer").finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NotificationEvent {
+    Started(NotificationServer),
+    Received(Notification),
+    /// The sender took it back.
+    Closed(u32),
+}
+
+struct Server {
+    events: mpsc::UnboundedSender<NotificationEvent>,
+    last_id: u32,
+    /// Ids handed out and not closed yet.
+    open: HashSet<u32>,
+}
+
+impl Server {
+    /// The next id not in use. Zero means "no notification", so it's
+    /// skipped on wrap around.
+    fn next_id(&mut self) -> u32 {
+        loop {
+            self.last_id = self.last_id.checked_add(1).unwrap_or(1);
+            if !self.open.contains(&self.last_id) {
+                return self.last_id;
+            }
+        }
+    }
+}
+
+#[zbus::interface(name = "org.freedesktop.Notifications")]
+impl Server {
+    /// `persistence` as they're kept in the panel's history. A popup timing
+    /// out only leaves the screen, so `NotificationClosed` goes out once it's
+    /// gone from the history too, never with reason 1, expired.
+    fn get_capabilities(&self) -> Vec<&str> {
+        vec!["actions", "body", "persistence"]
+    }
+
+    fn get_server_information(&self) -> (&str, &str, &str, &str) {
+        ("wawi", "wawi", env!("CARGO_PKG_VERSION"), "1.2")
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &mut self,
+        app_name: String,
+        replaces_id: u32,
+        _app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        // Replacing one that's gone, or was never there, makes a new one.
+        let id = if self.open.contains(&replaces_id) {
+            replaces_id
+        } else {
+            self.next_id()
+        };
+        self.open.insert(id);
+
+        let desktop_entry = hints
+            .get("desktop-entry")
+            .and_then(|entry| entry.downcast_ref::<String>().ok())
+            .filter(|entry| !entry.is_empty());
+
+        let urgency = match hints
+            .get("urgency")
+            .and_then(|urgency| urgency.downcast_ref::<u8>().ok())
+        {
+            Some(0) => Urgency::Low,
+            Some(2) => Urgency::Critical,
+            _ => Urgency::Normal,
+        };
+
+        let timeout = match expire_timeout {
+            // Only -1 asks for the default, but other negative values are
+            // taken the same way. Critical ones are important enough to wait
+            // for the user.
+            ..=-1 if urgency == Urgency::Critical => None,
+            ..=-1 => Some(DEFAULT_TIMEOUT),
+            0 => None,
+            milliseconds => Some(Duration::from_millis(milliseconds.unsigned_abs().into())),
+        };
+
+        let actions = actions
+            .chunks_exact(2)
+            .map(|pair| (pair[0].clone(), pair[1].clone()))
+            .collect();
+
+        let _ = self
+            .events
+            .unbounded_send(NotificationEvent::Received(Notification {
+                id,
+                app_name,
+                desktop_entry,
+                summary,
+                body,
+                actions,
+                urgency,
+                timeout,
+                received: std::time::SystemTime::now(),
+            }));
+
+        id
+    }
+
+    async fn close_notification(
+        &mut self,
+        #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+        id: u32,
+    ) -> zbus::fdo::Result<()> {
+        self.open.remove(&id);
+        let _ = self.events.unbounded_send(NotificationEvent::Closed(id));
+        Self::notification_closed(&ctxt, id, CloseReason::Closed as u32).await?;
+
+        Ok(())
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(
+        ctxt: &zbus::SignalContext<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn action_invoked(
+        ctxt: &zbus::SignalContext<'_>,
+        id: u32,
+        action_key: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Sends a notification of our own, which goes through the server like any
+/// other: into the history, and a popup unless Do Not Disturb holds it back.
+pub async fn send(summary: String, body: String, urgency: Urgency) -> Result<u32, String> {
+    let urgency: u8 = match urgency {
+        Urgency::Low => 0,
+        Urgency::Normal => 1,
+        Urgency::Critical => 2,
+    };
+
+    let result = async {
+        let conn = zbus::Connection::session().await?;
+        NotificationsProxy::new(&conn)
+            .await?
+            .notify(
+                "wawi",
+                0,
+                "",
+                &summary,
+                &body,
+                &[],
+                HashMap::from([("urgency", Value::from(urgency))]),
+                -1,
+            )
+            .await
+    };
+
+    result.await.map_err(|err| {
+        eprintln!("Couldn't send the {summary} notification: {err}");
+        err.to_string()
+    })
+}
+
+/// Tells the sender the notification is gone.
+pub async fn close(server: NotificationServer, id: u32, reason: CloseReason) -> Result<(), String> {
+    server.closed(id, reason).await.map_err(|err| {
+        eprintln!("Couldn't close notification {id}: {err}");
+        err.to_string()
+    })
+}
+
+/// Tells the sender the user picked `action_key`. The notification is closed
+/// too, as it has served its purpose.
+pub async fn invoke_action(
+    server: NotificationServer,
+    id: u32,
+    action_key: String,
+) -> Result<(), String> {
+    let result = async {
+        let ctxt = zbus::SignalContext::new(&server.0, NOTIFICATIONS_PATH)?;
+        Server::action_invoked(&ctxt, id, &action_key).await?;
+        server.closed(id, CloseReason::Dismissed).await
+    };
+
+    result.await.map_err(|err| {
+        eprintln!("Couldn't invoke {action_key} on notification {id}: {err}");
+        err.to_string()
+    })
+}
+
+/// Serves notifications on `conn`, failing if another notification daemon
+/// already does. They keep coming for as long as the stream, which holds on
+/// to `conn`, is alive.
+pub async fn event_stream(
+    conn: zbus::Connection,
+) -> zbus::Result<impl futures::Stream<Item = NotificationEvent>> {
+    let (events, receiver) = mpsc::unbounded();
+    conn.object_server()
+        .at(
+            NOTIFICATIONS_PATH,
+            Server {
+                events,
+                last_id: 0,
+                open: HashSet::new(),
+            },
+        )
+        .await?;
+    conn.request_name(NOTIFICATIONS_NAME).await?;
+
+    let started = NotificationEvent::Started(NotificationServer(conn.clone()));
+
+    Ok(
+        futures::stream::once(async { started }).chain(futures::stream::unfold(
+            (conn, receiver),
+            |(conn, mut receiver)| async move {
+                let event = receiver.next().await?;
+                Some((event, (conn, receiver)))
+            },
+        )),
+    )
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<NotificationEvent>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("notifications", id),
+        async move {
+            let stream = match zbus::Connection::session().await {
+                Ok(conn) => event_stream(conn).await,
+                Err(err) => Err(err),
+            };
+
+            match stream {
+                Ok(stream) => stream.boxed(),
+                Err(err) => {
+                    eprintln!("Couldn't become the notification server: {err}");
+                    futures::stream::pending().boxed()
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::testing::{self, PrivateBus};
+
+    fn at(hour: u32, minute: u32) -> TimeOfDay {
+        TimeOfDay { hour, minute }
+    }
+
+    fn notification(app_name: &str, desktop_entry: Option<&str>, urgency: Urgency) -> Notification {
+        Notification {
+            id: 1,
+            app_name: app_name.to_owned(),
+            desktop_entry: desktop_entry.map(str::to_owned),
+            summary: "Summary".to_owned(),
+            body: String::new(),
+            actions: Vec::new(),
+            urgency,
+            timeout: None,
+            received: std::time::SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn schedules_wrap_around_midnight() {
+        let night = Schedule {
+            start: at(22, 0),
+            end: at(7, 30),
+        };
+
+        assert!(night.contains(at(22, 0)));
+        assert!(night.contains(at(23, 59)));
+        assert!(night.contains(at(0, 0)));
+        assert!(night.contains(at(7, 29)));
+        assert!(!night.contains(at(7, 30)));
+        assert!(!night.contains(at(12, 0)));
+        assert!(!night.contains(at(21, 59)));
+    }
+
+    #[test]
+    fn schedules_within_a_day() {
+        let meeting = Schedule {
+            start: at(9, 0),
+            end: at(10, 0),
+        };
+
+        assert!(meeting.contains(at(9, 0)));
+        assert!(!meeting.contains(at(10, 0)));
+        assert!(!meeting.contains(at(8, 59)));
+    }
+
+    #[test]
+    fn empty_schedules_never_apply() {
+        let empty = Schedule {
+            start: at(9, 0),
+            end: at(9, 0),
+        };
+
+        assert!(!empty.contains(at(9, 0)));
+        assert!(!empty.contains(at(21, 0)));
+    }
+
+    #[test]
+    fn everything_pops_up_outside_quiet_hours() {
+        let do_not_disturb = DoNotDisturb {
+            schedules: vec![Schedule {
+                start: at(22, 0),
+                end: at(7, 0),
+            }],
+            ..DoNotDisturb::default()
+        };
+        let chat = notification("Chat", None, Urgency::Low);
+
+        assert!(do_not_disturb.allows(&chat, at(12, 0)));
+        assert!(!do_not_disturb.allows(&chat, at(23, 0)));
+        assert_eq!(do_not_disturb.active_schedule(at(12, 0)), None);
+    }
+
+    #[test]
+    fn critical_ones_get_through_if_allowed() {
+        let mut do_not_disturb = DoNotDisturb {
+            enabled: true,
+            ..DoNotDisturb::default()
+        };
+        let battery = notification("wawi", None, Urgency::Critical);
+        let chat = notification("Chat", None, Urgency::Normal);
+
+        assert!(do_not_disturb.allows(&battery, at(12, 0)));
+        assert!(!do_not_disturb.allows(&chat, at(12, 0)));
+
+        do_not_disturb.allow_critical = false;
+        assert!(!do_not_disturb.allows(&battery, at(12, 0)));
+    }
+
+    #[test]
+    fn allowed_apps_match_the_name_or_desktop_entry() {
+        let do_not_disturb = DoNotDisturb {
+            enabled: true,
+            allowed_apps: vec!["signal".to_owned(), "org.gnome.Calendar".to_owned()],
+            ..DoNotDisturb::default()
+        };
+
+        let by_name = notification("Signal", None, Urgency::Normal);
+        let by_entry = notification("Calendar", Some("org.gnome.Calendar"), Urgency::Normal);
+        let other = notification("Chat", Some("org.example.Chat"), Urgency::Normal);
+
+        assert!(do_not_disturb.allows(&by_name, at(12, 0)));
+        assert!(do_not_disturb.allows(&by_entry, at(12, 0)));
+        assert!(!do_not_disturb.allows(&other, at(12, 0)));
+    }
+
+    /// What senders call besides `Notify`, which the proxy above has.
+    #[zbus::proxy(
+        interface = "org.freedesktop.Notifications",
+        default_service = "org.freedesktop.Notifications",
+        default_path = "/org/freedesktop/Notifications"
+    )]
+    trait Sender {
+        fn close_notification(&self, id: u32) -> zbus::Result<()>;
+
+        fn get_capabilities(&self) -> zbus::Result<Vec<String>>;
+
+        #[zbus(signal)]
+        fn notification_closed(&self, id: u32, reason: u32) -> zbus::Result<()>;
+    }
+
+    struct Fixture {
+        events: futures::stream::BoxStream<'static, NotificationEvent>,
+        server: NotificationServer,
+        client: zbus::Connection,
+        // Dropped last, the connections need it.
+        _bus: PrivateBus,
+    }
+
+    async fn start() -> Fixture {
+        let bus = PrivateBus::start();
+        let mut events = event_stream(bus.connect().await).await.unwrap().boxed();
+
+        let NotificationEvent::Started(server) = testing::next(&mut events).await else {
+            panic!("the server should start first");
+        };
+        let client = bus.connect().await;
+
+        Fixture {
+            events,
+            server,
+            client,
+            _bus: bus,
+        }
+    }
+
+    impl Fixture {
+        async fn notify(
+            &self,
+            replaces_id: u32,
+            hints: HashMap<&str, Value<'_>>,
+            expire_timeout: i32,
+        ) -> u32 {
+            NotificationsProxy::new(&self.client)
+                .await
+                .unwrap()
+                .notify(
+                    "test",
+                    replaces_id,
+                    "",
+                    "Summary",
+                    "Body",
+                    &["default", "Open"],
+                    hints,
+                    expire_timeout,
+                )
+                .await
+                .unwrap()
+        }
+
+        async fn received(&mut self) -> Notification {
+            match testing::next(&mut self.events).await {
+                NotificationEvent::Received(notification) => notification,
+                event => panic!("expected a notification, got {event:?}"),
+            }
+        }
+
+        async fn sender(&self) -> SenderProxy<'static> {
+            SenderProxy::new(&self.client).await.unwrap()
+        }
+    }
+
+    #[tokio::test]
+    async fn ids_count_up_and_replacements_keep_theirs() {
+        let mut fixture = start().await;
+
+        assert_eq!(fixture.notify(0, HashMap::new(), -1).await, 1);
+        assert_eq!(fixture.notify(0, HashMap::new(), -1).await, 2);
+        assert_eq!(fixture.notify(1, HashMap::new(), -1).await, 1);
+        // Never handed out, so it's a new one.
+        assert_eq!(fixture.notify(42, HashMap::new(), -1).await, 3);
+
+        let ids = [
+            fixture.received().await.id,
+            fixture.received().await.id,
+            fixture.received().await.id,
+            fixture.received().await.id,
+        ];
+        assert_eq!(ids, [1, 2, 1, 3]);
+    }
+
+    #[tokio::test]
+    async fn reads_the_urgency_and_desktop_entry() {
+        let mut fixture = start().await;
+
+        let hints = HashMap::from([
+            ("urgency", Value::from(2u8)),
+            ("desktop-entry", Value::from("org.mozilla.firefox")),
+        ]);
+        fixture.notify(0, hints, -1).await;
+        let notification = fixture.received().await;
+        assert_eq!(notification.urgency, Urgency::Critical);
+        assert_eq!(
+            notification.desktop_entry.as_deref(),
+            Some("org.mozilla.firefox")
+        );
+        assert_eq!(
+            notification.actions,
+            vec![("default".to_owned(), "Open".to_owned())]
+        );
+
+        let hints = HashMap::from([
+            ("urgency", Value::from(0u8)),
+            ("desktop-entry", Value::from("")),
+        ]);
+        fixture.notify(0, hints, -1).await;
+        let notification = fixture.received().await;
+        assert_eq!(notification.urgency, Urgency::Low);
+        assert_eq!(notification.desktop_entry, None);
+
+        fixture.notify(0, HashMap::new(), -1).await;
+        assert_eq!(fixture.received().await.urgency, Urgency::Normal);
+    }
+
+    #[tokio::test]
+    async fn maps_the_expire_timeout() {
+        let mut fixture = start().await;
+        let critical = || HashMap::from([("urgency", Value::from(2u8))]);
+
+        for (hints, expire_timeout, timeout) in [
+            (HashMap::new(), -1, Some(DEFAULT_TIMEOUT)),
+            (HashMap::new(), -3000, Some(DEFAULT_TIMEOUT)),
+            (critical(), -1, None),
+            (HashMap::new(), 0, None),
+            (HashMap::new(), 1500, Some(Duration::from_millis(1500))),
+        ] {
+            fixture.notify(0, hints, expire_timeout).await;
+            assert_eq!(
+                fixture.received().await.timeout,
+                timeout,
+                "{expire_timeout}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn closing_tells_the_sender_and_frees_the_id() {
+        let mut fixture = start().await;
+        let sender = fixture.sender().await;
+        let mut closed = sender.receive_notification_closed().await.unwrap();
+
+        let id = fixture.notify(0, HashMap::new(), -1).await;
+        fixture.received().await;
+        sender.close_notification(id).await.unwrap();
+
+        assert!(matches!(
+            testing::next(&mut fixture.events).await,
+            NotificationEvent::Closed(closed_id) if closed_id == id
+        ));
+        let args = testing::next(&mut closed).await;
+        let args = args.args().unwrap();
+        assert_eq!((args.id, args.reason), (id, CloseReason::Closed as u32));
+
+        // Replacing it now makes a new one.
+        assert_eq!(fixture.notify(id, HashMap::new(), -1).await, id + 1);
+    }
+
+    #[tokio::test]
+    async fn dismissing_tells_the_sender() {
+        let mut fixture = start().await;
+        let sender = fixture.sender().await;
+        let mut closed = sender.receive_notification_closed().await.unwrap();
+
+        let id = fixture.notify(0, HashMap::new(), -1).await;
+        fixture.received().await;
+        close(fixture.server.clone(), id, CloseReason::Dismissed)
+            .await
+            .unwrap();
+
+        let args = testing::next(&mut closed).await;
+        let args = args.args().unwrap();
+        assert_eq!((args.id, args.reason), (id, CloseReason::Dismissed as u32));
+        assert_eq!(fixture.notify(id, HashMap::new(), -1).await, id + 1);
+    }
+
+    #[tokio::test]
+    async fn lists_its_capabilities() {
+        let fixture = start().await;
+
+        assert_eq!(
+            fixture.sender().await.get_capabilities().await.unwrap(),
+            vec!["actions", "body", "persistence"]
+        );
+    }
+}
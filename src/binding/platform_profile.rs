@@ -0,0 +1,304 @@
+use iced::futures::{self, channel::mpsc, FutureExt, StreamExt};
+use std::{
+    fs,
+    io::{self, Read, Seek},
+    os::fd::AsRawFd,
+    path::PathBuf,
+    time::Duration,
+};
+
+/// The kernel's `platform_profile`, which most laptops map to their fan curve
+/// and power limits. Everything is read relative to `root`, `/sys` normally,
+/// so a directory laid out the same way can stand in for it.
+#[derive(Debug, Clone)]
+pub struct PlatformProfile {
+    root: PathBuf,
+    /// How long to wait for the kernel to announce a change before looking
+    /// anyway, for files that never do, like the ones of a fake root.
+    poll_timeout: Duration,
+}
+
+impl PlatformProfile {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            poll_timeout: Duration::from_secs(5),
+        }
+    }
+
+    pub fn system() -> Self {
+        Self::new("/sys")
+    }
+
+    fn profile_path(&self) -> PathBuf {
+        self.root.join("firmware/acpi/platform_profile")
+    }
+
+    fn choices_path(&self) -> PathBuf {
+        self.root.join("firmware/acpi/platform_profile_choices")
+    }
+
+    pub fn read(&self) -> io::Result<PlatformProfileInfo> {
+        let active = fs::read_to_string(self.profile_path())?;
+        let choices = fs::read_to_string(self.choices_path())?;
+
+        Ok(PlatformProfileInfo::Available {
+            active: active.trim().to_owned(),
+            choices: choices.split_whitespace().map(str::to_owned).collect(),
+        })
+    }
+
+    /// Only root can write it, unless a udev rule says otherwise.
+    pub fn set(&self, profile: &str) -> io::Result<()> {
+        fs::write(self.profile_path(), profile)
+    }
+
+    /// Reading the attribute arms it, then `POLLPRI` fires once the kernel
+    /// calls `sysfs_notify` on it, e.g. after a Fn key changed the profile.
+    fn wait_for_change(&self, file: &mut fs::File) -> io::Result<()> {
+        let mut contents = String::new();
+        file.rewind()?;
+        file.read_to_string(&mut contents)?;
+
+        let mut poll_fd = libc::pollfd {
+            fd: file.as_raw_fd(),
+            events: libc::POLLPRI,
+            revents: 0,
+        };
+
+        // SAFETY: `poll_fd` refers to a descriptor that `file` keeps open.
+        let result = unsafe {
+            libc::poll(
+                &mut poll_fd,
+                1,
+                self.poll_timeout.as_millis() as libc::c_int,
+            )
+        };
+        if result < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlatformProfileInfo {
+    NotAvailable,
+    Available {
+        /// As the kernel names it, e.g. `low-power` or `balanced`.
+        active: String,
+        choices: Vec<String>,
+    },
+}
+
+impl PlatformProfileInfo {
+    /// The choice after the active one, wrapping around.
+    pub fn next(&self) -> Option<&str> {
+        let PlatformProfileInfo::Available { active, choices } = self else {
+            return None;
+        };
+        let index = choices.iter().position(|choice| choice == active);
+        let next = index.map_or(0, |index| (index + 1) % choices.len());
+
+        choices.get(next).map(String::as_str)
+    }
+}
+
+/// `balanced-performance` → `Balanced Performance`.
+pub fn display_name(profile: &str) -> String {
+    profile
+        .split('-')
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn read_or_not_available(platform_profile: &PlatformProfile) -> PlatformProfileInfo {
+    platform_profile.read().unwrap_or_else(|err| {
+        eprintln!("Couldn't read the platform profile: {err}");
+        PlatformProfileInfo::NotAvailable
+    })
+}
+
+/// Switches to `profile`, returning whatever is active afterwards.
+pub async fn set_profile(
+    platform_profile: PlatformProfile,
+    profile: String,
+) -> Result<PlatformProfileInfo, String> {
+    let result = tokio::task::spawn_blocking(move || {
+        platform_profile.set(&profile).map_err(|err| {
+            eprintln!("Couldn't switch to the {profile} platform profile: {err}");
+
+            match err.kind() {
+                io::ErrorKind::PermissionDenied => {
+                    "Not allowed to change the fan profile".to_owned()
+                }
+                _ => err.to_string(),
+            }
+        })?;
+
+        Ok(read_or_not_available(&platform_profile))
+    })
+    .await;
+
+    result.unwrap_or_else(|err| Err(err.to_string()))
+}
+
+/// Follows the platform profile under `platform_profile`'s root. Waiting
+/// blocks, so it happens on a thread of its own, which goes away within a
+/// poll timeout once the stream is dropped.
+pub fn event_stream(
+    platform_profile: PlatformProfile,
+) -> impl futures::Stream<Item = PlatformProfileInfo> {
+    let (sender, receiver) = mpsc::unbounded();
+
+    std::thread::spawn(move || {
+        let mut file = match fs::File::open(platform_profile.profile_path()) {
+            Ok(file) => file,
+            Err(err) => {
+                eprintln!("Couldn't open the platform profile: {err}");
+                let _ = sender.unbounded_send(PlatformProfileInfo::NotAvailable);
+                return;
+            }
+        };
+        let mut last = None;
+
+        while !sender.is_closed() {
+            let info = read_or_not_available(&platform_profile);
+
+            // Timeouts would repeat the same profile over and over.
+            if last.as_ref() != Some(&info) {
+                if sender.unbounded_send(info.clone()).is_err() {
+                    break;
+                }
+                last = Some(info);
+            }
+
+            // A failed read may well work next time, e.g. while the driver
+            // is busy, so it's tried again after a while.
+            if let Err(err) = platform_profile.wait_for_change(&mut file) {
+                eprintln!("Couldn't watch the platform profile: {err}");
+                std::thread::sleep(platform_profile.poll_timeout);
+            }
+        }
+    });
+
+    receiver.chain(futures::stream::pending())
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<PlatformProfileInfo>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("platform-profile", id),
+        async move { event_stream(PlatformProfile::system()) }.flatten_stream(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::testing::{self, FakeRoot};
+
+    fn fake_root(active: &str) -> FakeRoot {
+        let root = FakeRoot::new("platform-profile");
+        root.write("firmware/acpi/platform_profile", &format!("{active}\n"));
+        root.write(
+            "firmware/acpi/platform_profile_choices",
+            "low-power balanced performance\n",
+        );
+
+        root
+    }
+
+    fn available(active: &str) -> PlatformProfileInfo {
+        PlatformProfileInfo::Available {
+            active: active.to_owned(),
+            choices: vec![
+                "low-power".to_owned(),
+                "balanced".to_owned(),
+                "performance".to_owned(),
+            ],
+        }
+    }
+
+    #[test]
+    fn reads_the_active_profile_and_choices() {
+        let root = fake_root("balanced");
+
+        assert_eq!(
+            PlatformProfile::new(root.path()).read().unwrap(),
+            available("balanced")
+        );
+    }
+
+    #[test]
+    fn next_wraps_around() {
+        assert_eq!(available("low-power").next(), Some("balanced"));
+        assert_eq!(available("performance").next(), Some("low-power"));
+        // Something the choices don't list, e.g. a custom profile.
+        assert_eq!(available("custom").next(), Some("low-power"));
+        assert_eq!(PlatformProfileInfo::NotAvailable.next(), None);
+    }
+
+    #[test]
+    fn names_are_capitalized() {
+        assert_eq!(display_name("balanced-performance"), "Balanced Performance");
+        assert_eq!(display_name("quiet"), "Quiet");
+    }
+
+    #[tokio::test]
+    async fn set_writes_the_profile() {
+        let root = fake_root("balanced");
+
+        let info = set_profile(PlatformProfile::new(root.path()), "performance".to_owned()).await;
+
+        assert_eq!(info, Ok(available("performance")));
+        assert_eq!(root.read("firmware/acpi/platform_profile"), "performance");
+    }
+
+    #[tokio::test]
+    async fn set_reports_failures() {
+        let root = FakeRoot::new("platform-profile");
+
+        let info = set_profile(PlatformProfile::new(root.path()), "performance".to_owned()).await;
+
+        assert!(info.is_err());
+    }
+
+    #[tokio::test]
+    async fn keeps_watching_after_a_failed_read() {
+        let root = fake_root("balanced");
+        // The fake files never announce anything.
+        let platform_profile = PlatformProfile {
+            poll_timeout: Duration::from_millis(50),
+            ..PlatformProfile::new(root.path())
+        };
+        let mut stream = Box::pin(event_stream(platform_profile));
+        assert_eq!(testing::next(&mut stream).await, available("balanced"));
+
+        std::fs::remove_file(root.path().join("firmware/acpi/platform_profile_choices")).unwrap();
+        assert_eq!(
+            testing::next(&mut stream).await,
+            PlatformProfileInfo::NotAvailable
+        );
+
+        root.write(
+            "firmware/acpi/platform_profile_choices",
+            "low-power balanced performance\n",
+        );
+        root.write("firmware/acpi/platform_profile", "low-power\n");
+        assert_eq!(testing::next(&mut stream).await, available("low-power"));
+    }
+}
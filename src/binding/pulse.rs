@@ -0,0 +1,458 @@
+use iced::futures::{self, FutureExt, StreamExt};
+use serde::Deserialize;
+use std::{collections::HashMap, io, process::Stdio, time::Duration};
+use tokio::io::AsyncBufReadExt;
+
+// There's no stable D-Bus API for audio, so this goes through `pactl`, which
+// talks to both PulseAudio and PipeWire (through pipewire-pulse).
+async fn pactl(args: &[&str]) -> io::Result<String> {
+    let output = tokio::process::Command::new("pactl")
+        .args(args)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(io::Error::other(stderr.trim().to_owned()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+async fn list<T>(kind: &str) -> io::Result<Vec<T>>
+where
+    T: for<'de> Deserialize<'de>,
+{
+    let output = pactl(&["--format=json", "list", kind]).await?;
+
+    serde_json::from_str(&output).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ChannelVolume {
+    value_percent: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Device {
+    index: u32,
+    name: String,
+    description: String,
+    mute: bool,
+    volume: HashMap<String, ChannelVolume>,
+}
+
+/// A stream either playing to a sink or recording from a source.
+#[derive(Debug, Clone, Deserialize)]
+struct Stream {
+    index: u32,
+    source: Option<u32>,
+    #[serde(default)]
+    mute: bool,
+    #[serde(default)]
+    volume: HashMap<String, ChannelVolume>,
+    #[serde(default)]
+    properties: HashMap<String, serde_json::Value>,
+}
+
+impl Stream {
+    fn property(&self, key: &str) -> Option<&str> {
+        self.properties.get(key).and_then(|value| value.as_str())
+    }
+
+    fn application_name(&self) -> String {
+        self.property("application.name")
+            .or_else(|| self.property("application.process.binary"))
+            .or_else(|| self.property("media.name"))
+            .unwrap_or("Unknown")
+            .to_owned()
+    }
+}
+
+/// Average of every channel, as a percentage.
+fn average_volume(volume: &HashMap<String, ChannelVolume>) -> u32 {
+    let channels: Vec<u32> = volume
+        .values()
+        .filter_map(|channel| channel.value_percent.trim_end_matches('%').parse().ok())
+        .collect();
+
+    match channels.len() {
+        0 => 0,
+        len => channels.iter().sum::<u32>() / len as u32,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Volume {
+    pub volume: u32,
+    pub muted: bool,
+}
+
+impl From<&Device> for Volume {
+    fn from(device: &Device) -> Self {
+        Self {
+            volume: average_volume(&device.volume),
+            muted: device.mute,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Output,
+    Input,
+}
+
+impl Direction {
+    fn device_kind(self) -> &'static str {
+        match self {
+            Direction::Output => "sink",
+            Direction::Input => "source",
+        }
+    }
+
+    fn stream_kind(self) -> &'static str {
+        match self {
+            Direction::Output => "sink-input",
+            Direction::Input => "source-output",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AudioDevice {
+    pub name: String,
+    pub description: String,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlaybackStream {
+    pub index: u32,
+    pub application: String,
+    /// Freedesktop icon name, e.g. `firefox`.
+    pub icon_name: Option<String>,
+    pub volume: Volume,
+}
+
+impl From<&Stream> for PlaybackStream {
+    fn from(stream: &Stream) -> Self {
+        Self {
+            index: stream.index,
+            application: stream.application_name(),
+            icon_name: stream.property("application.icon_name").map(str::to_owned),
+            volume: Volume {
+                volume: average_volume(&stream.volume),
+                muted: stream.mute,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum AudioInfo {
+    NotAvailable,
+    Available {
+        output: Option<Volume>,
+        input: Option<Volume>,
+        /// Applications currently recording from a microphone.
+        recording: Vec<String>,
+        sinks: Vec<AudioDevice>,
+        sources: Vec<AudioDevice>,
+        playback: Vec<PlaybackStream>,
+    },
+}
+
+fn audio_devices<'a>(devices: impl Iterator<Item = &'a Device>, default: &str) -> Vec<AudioDevice> {
+    devices
+        .map(|device| AudioDevice {
+            name: device.name.clone(),
+            description: device.description.clone(),
+            is_default: device.name == default,
+        })
+        .collect()
+}
+
+async fn audio_info() -> io::Result<AudioInfo> {
+    let default_sink = pactl(&["get-default-sink"]).await?;
+    let default_source = pactl(&["get-default-source"]).await?;
+    let sinks: Vec<Device> = list("sinks").await?;
+    let sources: Vec<Device> = list("sources").await?;
+    let source_outputs: Vec<Stream> = list("source-outputs").await?;
+    let sink_inputs: Vec<Stream> = list("sink-inputs").await?;
+
+    let output = sinks
+        .iter()
+        .find(|sink| sink.name == default_sink.trim())
+        .map(Volume::from);
+    let input = sources
+        .iter()
+        .find(|source| source.name == default_source.trim())
+        .map(Volume::from);
+
+    // Recording a sink monitor (screen recorders, visualizers) doesn't use the mic.
+    let mut recording: Vec<String> = source_outputs
+        .iter()
+        .filter(|stream| {
+            sources
+                .iter()
+                .find(|source| Some(source.index) == stream.source)
+                .is_some_and(|source| !source.name.ends_with(".monitor"))
+        })
+        .map(Stream::application_name)
+        .collect();
+    recording.sort();
+    recording.dedup();
+
+    let sinks = audio_devices(sinks.iter(), default_sink.trim());
+    let sources = audio_devices(
+        sources
+            .iter()
+            .filter(|source| !source.name.ends_with(".monitor")),
+        default_source.trim(),
+    );
+
+    let playback = sink_inputs.iter().map(PlaybackStream::from).collect();
+
+    Ok(AudioInfo::Available {
+        output,
+        input,
+        recording,
+        sinks,
+        sources,
+        playback,
+    })
+}
+
+async fn try_set_default_device(
+    direction: Direction,
+    name: &str,
+    move_streams: bool,
+) -> io::Result<()> {
+    let set_default = format!("set-default-{}", direction.device_kind());
+    pactl(&[&set_default, name]).await?;
+
+    if move_streams {
+        let mut streams: Vec<Stream> = list(&format!("{}s", direction.stream_kind())).await?;
+        let move_stream = format!("move-{}", direction.stream_kind());
+
+        // Streams recording a sink monitor would lose what they're recording.
+        if direction == Direction::Input {
+            let sources: Vec<Device> = list("sources").await?;
+            streams.retain(|stream| {
+                !sources
+                    .iter()
+                    .find(|source| Some(source.index) == stream.source)
+                    .is_some_and(|source| source.name.ends_with(".monitor"))
+            });
+        }
+
+        // One stream refusing to move shouldn't keep the others behind.
+        for stream in streams {
+            if let Err(err) = pactl(&[&move_stream, &stream.index.to_string(), name]).await {
+                eprintln!(
+                    "Couldn't move {} to {name}: {err}",
+                    stream.application_name()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Makes `name` the default device, optionally moving the streams that are
+/// already playing or recording over to it. Returns the default device
+/// afterwards, which is still the old one if switching failed.
+pub async fn set_default_device(direction: Direction, name: String, move_streams: bool) -> String {
+    let Err(err) = try_set_default_device(direction, &name, move_streams).await else {
+        return name;
+    };
+    eprintln!("Couldn't switch the default audio device: {err}");
+
+    let get_default = format!("get-default-{}", direction.device_kind());
+    match pactl(&[&get_default]).await {
+        Ok(default) => default.trim().to_owned(),
+        Err(err) => {
+            eprintln!("Couldn't query the default audio device: {err}");
+            name
+        }
+    }
+}
+
+pub async fn set_sink_volume(volume: u32) -> u32 {
+    let volume_arg = format!("{volume}%");
+
+    if let Err(err) = pactl(&["set-sink-volume", "@DEFAULT_SINK@", &volume_arg]).await {
+        eprintln!("Couldn't set the master volume: {err}");
+    }
+
+    volume
+}
+
+pub async fn set_sink_mute(muted: bool) -> bool {
+    let muted_arg = if muted { "1" } else { "0" };
+
+    if let Err(err) = pactl(&["set-sink-mute", "@DEFAULT_SINK@", muted_arg]).await {
+        eprintln!("Couldn't mute the master volume: {err}");
+    }
+
+    muted
+}
+
+pub async fn set_source_volume(volume: u32) -> u32 {
+    let volume_arg = format!("{volume}%");
+
+    if let Err(err) = pactl(&["set-source-volume", "@DEFAULT_SOURCE@", &volume_arg]).await {
+        eprintln!("Couldn't set the microphone volume: {err}");
+    }
+
+    volume
+}
+
+pub async fn set_source_mute(muted: bool) -> bool {
+    let muted_arg = if muted { "1" } else { "0" };
+
+    if let Err(err) = pactl(&["set-source-mute", "@DEFAULT_SOURCE@", muted_arg]).await {
+        eprintln!("Couldn't mute the microphone: {err}");
+    }
+
+    muted
+}
+
+pub async fn set_stream_volume(index: u32, volume: u32) -> (u32, u32) {
+    let volume_arg = format!("{volume}%");
+
+    if let Err(err) = pactl(&["set-sink-input-volume", &index.to_string(), &volume_arg]).await {
+        eprintln!("Couldn't set the stream volume: {err}");
+    }
+
+    (index, volume)
+}
+
+pub async fn set_stream_mute(index: u32, muted: bool) -> (u32, bool) {
+    let muted_arg = if muted { "1" } else { "0" };
+
+    if let Err(err) = pactl(&["set-sink-input-mute", &index.to_string(), muted_arg]).await {
+        eprintln!("Couldn't mute the stream: {err}");
+    }
+
+    (index, muted)
+}
+
+/// Lines look like `Event 'change' on sink #56`.
+fn is_relevant(event: &str) -> bool {
+    [
+        " on sink #",
+        " on source #",
+        " on sink-input #",
+        " on source-output #",
+        " on server",
+    ]
+    .iter()
+    .any(|facility| event.contains(facility))
+}
+
+/// How long to wait for the rest of a burst of events, e.g. the several sent
+/// for every slider tick, before querying the server once for all of them.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+/// How long to wait before starting `pactl subscribe` again once it exits,
+/// e.g. when the audio server restarts.
+const RESTART_DELAY: Duration = Duration::from_secs(1);
+
+type EventLines = tokio::io::Lines<tokio::io::BufReader<tokio::process::ChildStdout>>;
+
+fn subscribe() -> io::Result<(tokio::process::Child, EventLines)> {
+    let mut child = tokio::process::Command::new("pactl")
+        .arg("subscribe")
+        .stdout(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::BrokenPipe, "pactl has no stdout"))?;
+
+    Ok((child, tokio::io::BufReader::new(stdout).lines()))
+}
+
+/// Waits for a relevant event and whatever follows it within [`DEBOUNCE`].
+/// `Err` once `pactl subscribe` has exited.
+async fn next_events(lines: &mut EventLines) -> io::Result<()> {
+    loop {
+        match lines.next_line().await? {
+            Some(line) if is_relevant(&line) => break,
+            Some(_) => continue,
+            None => return Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+        }
+    }
+
+    // Whatever comes next is folded into this update, relevant or not.
+    while let Ok(line) = tokio::time::timeout(DEBOUNCE, lines.next_line()).await {
+        if line?.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+async fn event_stream() -> io::Result<impl futures::Stream<Item = AudioInfo>> {
+    let subscribed = subscribe()?;
+    let initial = audio_info().await?;
+
+    // The child is carried along so it's killed once the stream is dropped.
+    let events = futures::stream::unfold(Some(subscribed), |subscribed| async move {
+        let (mut child, mut lines) = match subscribed {
+            Some(subscribed) => subscribed,
+            None => loop {
+                tokio::time::sleep(RESTART_DELAY).await;
+
+                match subscribe() {
+                    Ok(subscribed) => break subscribed,
+                    Err(err) => eprintln!("Couldn't watch the audio server: {err}"),
+                }
+            },
+        };
+
+        match next_events(&mut lines).await {
+            Ok(()) => Some(((), Some((child, lines)))),
+            Err(err) => {
+                eprintln!("Stopped watching the audio server, restarting: {err}");
+                let _ = child.kill().await;
+                // Something may well have changed while nobody was watching.
+                Some(((), None))
+            }
+        }
+    });
+
+    // A failed query keeps the last state, the next event will most likely
+    // bring a good one.
+    let updates = events.filter_map(|_| async {
+        audio_info()
+            .await
+            .inspect_err(|err| eprintln!("Couldn't query the audio server: {err}"))
+            .ok()
+    });
+
+    Ok(futures::stream::once(async { initial }).chain(updates))
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<AudioInfo>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("pulse", id),
+        async move {
+            match event_stream().await {
+                Ok(stream) => stream,
+                Err(err) => {
+                    eprintln!("An error has ocurred: {err}");
+                    futures::future::pending().await
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
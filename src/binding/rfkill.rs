@@ -0,0 +1,544 @@
+use iced::futures::{self, channel::mpsc, FutureExt, StreamExt};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, Read, Write},
+    os::fd::AsRawFd,
+    time::Duration,
+};
+
+/// How long a read waits before checking whether anyone is still listening.
+const READ_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Raw access to the kernel's rfkill events. Implemented over `/dev/rfkill`
+/// by [`RfkillDevice`], but anything producing the same events will do.
+pub trait RfkillTransport {
+    /// Blocks until there's an event, or `None` once `timeout` has passed.
+    /// Opening the device replays an `Add` for every radio already present.
+    fn read_event(&mut self, timeout: Duration) -> io::Result<Option<RfkillEvent>>;
+
+    fn write_event(&mut self, event: &RfkillEvent) -> io::Result<()>;
+}
+
+pub struct RfkillDevice {
+    file: fs::File,
+}
+
+impl RfkillDevice {
+    const PATH: &'static str = "/dev/rfkill";
+
+    pub fn open() -> io::Result<Self> {
+        let file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(Self::PATH)?;
+
+        Ok(Self { file })
+    }
+}
+
+impl RfkillTransport for RfkillDevice {
+    fn read_event(&mut self, timeout: Duration) -> io::Result<Option<RfkillEvent>> {
+        let mut poll_fd = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+
+        // SAFETY: `poll_fd` refers to a descriptor that `file` keeps open.
+        let result = unsafe { libc::poll(&mut poll_fd, 1, timeout.as_millis() as libc::c_int) };
+        if result < 0 {
+            let err = io::Error::last_os_error();
+            return match err.kind() {
+                io::ErrorKind::Interrupted => Ok(None),
+                _ => Err(err),
+            };
+        }
+        if result == 0 {
+            return Ok(None);
+        }
+
+        let mut buffer = [0u8; RfkillEvent::SIZE];
+        self.file.read_exact(&mut buffer)?;
+
+        Ok(Some(RfkillEvent::from_bytes(buffer)))
+    }
+
+    fn write_event(&mut self, event: &RfkillEvent) -> io::Result<()> {
+        self.file.write_all(&event.to_bytes())
+    }
+}
+
+/// `enum rfkill_operation`, from `linux/rfkill.h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RfkillOp {
+    Add,
+    Del,
+    Change,
+    ChangeAll,
+    Unknown(u8),
+}
+
+impl From<u8> for RfkillOp {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Add,
+            1 => Self::Del,
+            2 => Self::Change,
+            3 => Self::ChangeAll,
+            other => Self::Unknown(other),
+        }
+    }
+}
+
+impl From<RfkillOp> for u8 {
+    fn from(op: RfkillOp) -> Self {
+        match op {
+            RfkillOp::Add => 0,
+            RfkillOp::Del => 1,
+            RfkillOp::Change => 2,
+            RfkillOp::ChangeAll => 3,
+            RfkillOp::Unknown(other) => other,
+        }
+    }
+}
+
+/// `struct rfkill_event`, the original 8 byte layout every kernel accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RfkillEvent {
+    pub index: u32,
+    /// `enum rfkill_type`, 0 meaning every type.
+    pub kind: u8,
+    pub op: RfkillOp,
+    pub soft: bool,
+    pub hard: bool,
+}
+
+impl RfkillEvent {
+    const SIZE: usize = 8;
+    const TYPE_ALL: u8 = 0;
+
+    fn from_bytes(bytes: [u8; Self::SIZE]) -> Self {
+        Self {
+            index: u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            kind: bytes[4],
+            op: RfkillOp::from(bytes[5]),
+            soft: bytes[6] != 0,
+            hard: bytes[7] != 0,
+        }
+    }
+
+    fn to_bytes(self) -> [u8; Self::SIZE] {
+        let index = self.index.to_ne_bytes();
+
+        [
+            index[0],
+            index[1],
+            index[2],
+            index[3],
+            self.kind,
+            u8::from(self.op),
+            self.soft as u8,
+            self.hard as u8,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Radio {
+    pub index: u32,
+    pub soft_blocked: bool,
+    /// Set by a hardware kill switch, which software can't undo.
+    pub hard_blocked: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum RfkillInfo {
+    NotAvailable,
+    Available {
+        /// Every radio is blocked, one way or another.
+        airplane_mode: bool,
+        /// A kill switch is holding at least one radio down.
+        hard_blocked: bool,
+        radios: Vec<Radio>,
+    },
+}
+
+impl RfkillInfo {
+    fn from_radios(radios: &HashMap<u32, Radio>) -> Self {
+        let mut radios: Vec<Radio> = radios.values().copied().collect();
+        radios.sort_by_key(|radio| radio.index);
+
+        RfkillInfo::Available {
+            airplane_mode: !radios.is_empty()
+                && radios
+                    .iter()
+                    .all(|radio| radio.soft_blocked || radio.hard_blocked),
+            hard_blocked: radios.iter().any(|radio| radio.hard_blocked),
+            radios,
+        }
+    }
+}
+
+fn apply(radios: &mut HashMap<u32, Radio>, event: RfkillEvent) {
+    match event.op {
+        RfkillOp::Add | RfkillOp::Change => {
+            radios.insert(
+                event.index,
+                Radio {
+                    index: event.index,
+                    soft_blocked: event.soft,
+                    hard_blocked: event.hard,
+                },
+            );
+        }
+        RfkillOp::Del => {
+            radios.remove(&event.index);
+        }
+        RfkillOp::ChangeAll | RfkillOp::Unknown(_) => {}
+    }
+}
+
+/// Soft-blocks every radio, current and future ones alike.
+pub fn block_all(transport: &mut impl RfkillTransport) -> io::Result<()> {
+    transport.write_event(&RfkillEvent {
+        index: 0,
+        kind: RfkillEvent::TYPE_ALL,
+        op: RfkillOp::ChangeAll,
+        soft: true,
+        hard: false,
+    })
+}
+
+/// Puts back the soft-block state of `radios`, as it was before [`block_all`].
+/// Without anything to go back to, every radio is unblocked.
+pub fn restore(transport: &mut impl RfkillTransport, radios: &[Radio]) -> io::Result<()> {
+    if radios.is_empty() {
+        return transport.write_event(&RfkillEvent {
+            index: 0,
+            kind: RfkillEvent::TYPE_ALL,
+            op: RfkillOp::ChangeAll,
+            soft: false,
+            hard: false,
+        });
+    }
+
+    for radio in radios {
+        let result = transport.write_event(&RfkillEvent {
+            index: radio.index,
+            kind: RfkillEvent::TYPE_ALL,
+            op: RfkillOp::Change,
+            soft: radio.soft_blocked,
+            hard: false,
+        });
+
+        // The radio may be gone by now, e.g. an unplugged USB dongle.
+        if let Err(err) = result {
+            eprintln!("Couldn't restore rfkill{}: {err}", radio.index);
+        }
+    }
+
+    Ok(())
+}
+
+/// Enables airplane mode, or disables it going back to `previous`.
+/// Returns whether airplane mode is now on.
+pub async fn set_airplane_mode(enabled: bool, previous: Vec<Radio>) -> bool {
+    let result = tokio::task::spawn_blocking(move || {
+        let mut device = RfkillDevice::open()?;
+
+        if enabled {
+            block_all(&mut device)
+        } else {
+            restore(&mut device, &previous)
+        }
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => enabled,
+        Ok(Err(err)) => {
+            eprintln!("Couldn't toggle airplane mode: {err}");
+            !enabled
+        }
+        Err(err) => {
+            eprintln!("Couldn't toggle airplane mode: {err}");
+            !enabled
+        }
+    }
+}
+
+/// Follows the radios behind `transport`. Reads block, so they happen on a
+/// thread of their own, which goes away along with `transport` shortly after
+/// the stream is dropped.
+pub fn event_stream<T>(mut transport: T) -> impl futures::Stream<Item = RfkillInfo>
+where
+    T: RfkillTransport + Send + 'static,
+{
+    let (sender, receiver) = mpsc::unbounded();
+
+    std::thread::spawn(move || {
+        while !sender.is_closed() {
+            let event = match transport.read_event(READ_TIMEOUT) {
+                Ok(Some(event)) => Ok(event),
+                Ok(None) => continue,
+                Err(err) => Err(err),
+            };
+            let failed = event.is_err();
+
+            if sender.unbounded_send(event).is_err() || failed {
+                break;
+            }
+        }
+    });
+
+    // The initial burst of `Add` events is coalesced into a single update.
+    receiver
+        .ready_chunks(32)
+        .scan(HashMap::new(), |radios, events| {
+            let mut info = None;
+
+            for event in events {
+                match event {
+                    Ok(event) => apply(radios, event),
+                    Err(err) => {
+                        eprintln!("Couldn't read rfkill events: {err}");
+                        info = Some(RfkillInfo::NotAvailable);
+                    }
+                }
+            }
+
+            let info = info.unwrap_or_else(|| RfkillInfo::from_radios(radios));
+            futures::future::ready(Some(info))
+        })
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<RfkillInfo>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("rfkill", id),
+        async move {
+            match RfkillDevice::open() {
+                Ok(device) => event_stream(device).boxed(),
+                Err(err) => {
+                    eprintln!("Couldn't open {}: {err}", RfkillDevice::PATH);
+                    futures::stream::once(async { RfkillInfo::NotAvailable })
+                        .chain(futures::stream::pending())
+                        .boxed()
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::testing;
+    use std::{
+        collections::{BTreeMap, VecDeque},
+        sync::{Arc, Mutex},
+    };
+
+    /// What the kernel keeps track of: every radio's `(soft, hard)` blocks,
+    /// and the events not read yet.
+    #[derive(Default)]
+    struct FakeKernel {
+        radios: BTreeMap<u32, (bool, bool)>,
+        events: VecDeque<RfkillEvent>,
+        open: usize,
+    }
+
+    impl FakeKernel {
+        fn change(&mut self, index: u32, soft: bool) {
+            let Some((blocked, hard)) = self.radios.get_mut(&index) else {
+                return;
+            };
+            *blocked = soft;
+
+            self.events.push_back(RfkillEvent {
+                index,
+                kind: 1,
+                op: RfkillOp::Change,
+                soft,
+                hard: *hard,
+            });
+        }
+    }
+
+    /// A handle on `/dev/rfkill`, as far as [`RfkillTransport`] can tell.
+    struct FakeRfkill(Arc<Mutex<FakeKernel>>);
+
+    impl FakeRfkill {
+        fn open(kernel: &Arc<Mutex<FakeKernel>>) -> Self {
+            let mut state = kernel.lock().unwrap();
+            state.open += 1;
+
+            let added: Vec<RfkillEvent> = state
+                .radios
+                .iter()
+                .map(|(&index, &(soft, hard))| RfkillEvent {
+                    index,
+                    kind: 1,
+                    op: RfkillOp::Add,
+                    soft,
+                    hard,
+                })
+                .collect();
+            state.events.extend(added);
+            drop(state);
+
+            Self(Arc::clone(kernel))
+        }
+    }
+
+    impl Drop for FakeRfkill {
+        fn drop(&mut self) {
+            self.0.lock().unwrap().open -= 1;
+        }
+    }
+
+    impl RfkillTransport for FakeRfkill {
+        fn read_event(&mut self, timeout: Duration) -> io::Result<Option<RfkillEvent>> {
+            if let Some(event) = self.0.lock().unwrap().events.pop_front() {
+                return Ok(Some(event));
+            }
+
+            std::thread::sleep(timeout.min(Duration::from_millis(10)));
+            Ok(None)
+        }
+
+        fn write_event(&mut self, event: &RfkillEvent) -> io::Result<()> {
+            let mut kernel = self.0.lock().unwrap();
+
+            match event.op {
+                RfkillOp::ChangeAll => {
+                    let indices: Vec<u32> = kernel.radios.keys().copied().collect();
+                    for index in indices {
+                        kernel.change(index, event.soft);
+                    }
+                }
+                RfkillOp::Change if kernel.radios.contains_key(&event.index) => {
+                    kernel.change(event.index, event.soft);
+                }
+                RfkillOp::Change => return Err(io::Error::from(io::ErrorKind::NotFound)),
+                _ => return Err(io::Error::from(io::ErrorKind::InvalidInput)),
+            }
+
+            Ok(())
+        }
+    }
+
+    fn kernel(radios: &[(u32, bool, bool)]) -> Arc<Mutex<FakeKernel>> {
+        Arc::new(Mutex::new(FakeKernel {
+            radios: radios
+                .iter()
+                .map(|&(index, soft, hard)| (index, (soft, hard)))
+                .collect(),
+            ..FakeKernel::default()
+        }))
+    }
+
+    /// The next update matching `matches`, skipping those in between.
+    async fn next_info<S>(stream: &mut S, matches: impl Fn(bool, &[Radio]) -> bool) -> Vec<Radio>
+    where
+        S: futures::Stream<Item = RfkillInfo> + Unpin,
+    {
+        loop {
+            if let RfkillInfo::Available {
+                airplane_mode,
+                radios,
+                ..
+            } = testing::next(stream).await
+            {
+                if matches(airplane_mode, &radios) {
+                    return radios;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn events_survive_a_round_trip() {
+        let event = RfkillEvent {
+            index: 3,
+            kind: 2,
+            op: RfkillOp::Change,
+            soft: true,
+            hard: false,
+        };
+
+        assert_eq!(RfkillEvent::from_bytes(event.to_bytes()), event);
+        assert_eq!(RfkillOp::from(9), RfkillOp::Unknown(9));
+    }
+
+    #[tokio::test]
+    async fn airplane_mode_restores_what_was_blocked_before() {
+        // Wi-Fi is on and Bluetooth was turned off by hand.
+        let kernel = kernel(&[(0, false, false), (1, true, false)]);
+        let mut stream = Box::pin(event_stream(FakeRfkill::open(&kernel)));
+        let mut control = FakeRfkill::open(&kernel);
+
+        let before = next_info(&mut stream, |_, radios| radios.len() == 2).await;
+
+        block_all(&mut control).unwrap();
+        next_info(&mut stream, |airplane_mode, _| airplane_mode).await;
+
+        restore(&mut control, &before).unwrap();
+        let after = next_info(&mut stream, |airplane_mode, _| !airplane_mode).await;
+        assert_eq!(after, before);
+    }
+
+    #[tokio::test]
+    async fn restoring_nothing_unblocks_everything() {
+        let kernel = kernel(&[(0, true, false), (1, true, false)]);
+        let mut stream = Box::pin(event_stream(FakeRfkill::open(&kernel)));
+
+        next_info(&mut stream, |airplane_mode, _| airplane_mode).await;
+        restore(&mut FakeRfkill::open(&kernel), &[]).unwrap();
+
+        let radios = next_info(&mut stream, |airplane_mode, _| !airplane_mode).await;
+        assert!(radios.iter().all(|radio| !radio.soft_blocked));
+    }
+
+    #[test]
+    fn a_kill_switch_keeps_airplane_mode_on() {
+        let radios = HashMap::from([(
+            0,
+            Radio {
+                index: 0,
+                soft_blocked: false,
+                hard_blocked: true,
+            },
+        )]);
+
+        assert!(matches!(
+            RfkillInfo::from_radios(&radios),
+            RfkillInfo::Available {
+                airplane_mode: true,
+                hard_blocked: true,
+                ..
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn the_reader_goes_away_with_the_stream() {
+        let kernel = kernel(&[(0, false, false)]);
+        let mut stream = Box::pin(event_stream(FakeRfkill::open(&kernel)));
+        next_info(&mut stream, |_, radios| radios.len() == 1).await;
+
+        drop(stream);
+
+        for _ in 0..100 {
+            if kernel.lock().unwrap().open == 0 {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("the reader thread should have closed its transport");
+    }
+}
@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Temperatures and fans from `hwmon` and thermal zones. Everything is read
+/// relative to `root`, `/sys` normally, so a directory laid out the same way
+/// can stand in for it.
+#[derive(Debug, Clone)]
+pub struct Sensors {
+    root: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SensorKind {
+    Cpu,
+    Gpu,
+    Disk,
+    Other,
+}
+
+impl SensorKind {
+    /// By the name of the driver behind the sensor.
+    fn from_chip(chip: &str) -> Self {
+        match chip {
+            "coretemp" | "k10temp" | "zenpower" | "x86_pkg_temp" | "cpu_thermal" | "TCPU" => {
+                SensorKind::Cpu
+            }
+            "amdgpu" | "radeon" | "nouveau" => SensorKind::Gpu,
+            "nvme" | "drivetemp" => SensorKind::Disk,
+            _ => SensorKind::Other,
+        }
+    }
+}
+
+impl std::fmt::Display for SensorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SensorKind::Cpu => write!(f, "CPU"),
+            SensorKind::Gpu => write!(f, "GPU"),
+            SensorKind::Disk => write!(f, "Disk"),
+            SensorKind::Other => write!(f, "Other"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Temperature {
+    /// Driver name, e.g. `coretemp` or `nvme`.
+    pub chip: String,
+    /// E.g. `Package id 0`, `edge` or `Composite`; `temp2` and such for
+    /// sensors the driver doesn't name.
+    pub label: String,
+    pub kind: SensorKind,
+    pub celsius: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Fan {
+    pub chip: String,
+    pub label: String,
+    pub rpm: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SensorReadings {
+    pub temperatures: Vec<Temperature>,
+    pub fans: Vec<Fan>,
+}
+
+impl SensorReadings {
+    pub fn hottest(&self, kind: SensorKind) -> Option<&Temperature> {
+        self.temperatures
+            .iter()
+            .filter(|temperature| temperature.kind == kind)
+            .max_by(|a, b| a.celsius.total_cmp(&b.celsius))
+    }
+
+    /// The fastest spinning one, which is the one you'd hear.
+    pub fn loudest_fan(&self) -> Option<&Fan> {
+        self.fans.iter().max_by_key(|fan| fan.rpm)
+    }
+}
+
+/// Over-temperature thresholds in °C, `None` turning the alert off. Read from
+/// `thermal-alerts.json` in the config directory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ThermalAlerts {
+    pub cpu: Option<f64>,
+    pub gpu: Option<f64>,
+    pub disk: Option<f64>,
+    pub other: Option<f64>,
+}
+
+impl Default for ThermalAlerts {
+    fn default() -> Self {
+        Self {
+            cpu: Some(90.0),
+            gpu: Some(85.0),
+            disk: Some(70.0),
+            other: None,
+        }
+    }
+}
+
+impl ThermalAlerts {
+    /// How far below its threshold a sensor has to cool down before its
+    /// alert goes away, so one hovering around it doesn't flap.
+    const HYSTERESIS: f64 = 5.0;
+
+    fn threshold(&self, kind: SensorKind) -> Option<f64> {
+        match kind {
+            SensorKind::Cpu => self.cpu,
+            SensorKind::Gpu => self.gpu,
+            SensorKind::Disk => self.disk,
+            SensorKind::Other => self.other,
+        }
+    }
+
+    /// The hottest sensor of every kind over its threshold. `previous` are
+    /// the alerts already raised, which stay up until the sensor cools down.
+    pub fn check(&self, readings: &SensorReadings, previous: &[Temperature]) -> Vec<Temperature> {
+        [
+            SensorKind::Cpu,
+            SensorKind::Gpu,
+            SensorKind::Disk,
+            SensorKind::Other,
+        ]
+        .into_iter()
+        .filter_map(|kind| {
+            let threshold = self.threshold(kind)?;
+            let hottest = readings.hottest(kind)?;
+
+            let was_raised = previous.iter().any(|alert| alert.kind == kind);
+            let limit = if was_raised {
+                threshold - Self::HYSTERESIS
+            } else {
+                threshold
+            };
+
+            (hottest.celsius >= limit).then(|| hottest.clone())
+        })
+        .collect()
+    }
+}
+
+fn read_trimmed(path: &Path) -> io::Result<String> {
+    Ok(fs::read_to_string(path)?.trim().to_owned())
+}
+
+/// Millidegrees, as every sysfs temperature is given in.
+fn read_celsius(path: &Path) -> Option<f64> {
+    let millidegrees: i64 = read_trimmed(path).ok()?.parse().ok()?;
+    Some(millidegrees as f64 / 1000.0)
+}
+
+/// Entries of `dir` in a stable order, nothing if it can't be read.
+fn sorted_entries(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+
+    paths
+}
+
+/// `temp3_input` → `3`, for `prefix` `temp`.
+fn input_index<'a>(file_name: &'a str, prefix: &str) -> Option<&'a str> {
+    file_name.strip_prefix(prefix)?.strip_suffix("_input")
+}
+
+impl Sensors {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn system() -> Self {
+        Self::new("/sys")
+    }
+
+    pub fn read(&self) -> SensorReadings {
+        let mut readings = SensorReadings::default();
+
+        self.read_hwmon(&mut readings);
+        self.read_thermal_zones(&mut readings);
+
+        readings
+    }
+
+    fn read_hwmon(&self, readings: &mut SensorReadings) {
+        for device in sorted_entries(&self.root.join("class/hwmon")) {
+            let Ok(chip) = read_trimmed(&device.join("name")) else {
+                continue;
+            };
+            let label = |prefix: &str, index: &str| {
+                read_trimmed(&device.join(format!("{prefix}{index}_label")))
+                    .unwrap_or_else(|_| format!("{prefix}{index}"))
+            };
+
+            for path in sorted_entries(&device) {
+                let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                    continue;
+                };
+
+                // Reads fail while a device sleeps, e.g. a GPU in D3cold.
+                if let Some(index) = input_index(file_name, "temp") {
+                    if let Some(celsius) = read_celsius(&path) {
+                        readings.temperatures.push(Temperature {
+                            chip: chip.clone(),
+                            label: label("temp", index),
+                            kind: SensorKind::from_chip(&chip),
+                            celsius,
+                        });
+                    }
+                } else if let Some(index) = input_index(file_name, "fan") {
+                    if let Some(rpm) = read_trimmed(&path).ok().and_then(|rpm| rpm.parse().ok()) {
+                        readings.fans.push(Fan {
+                            chip: chip.clone(),
+                            label: label("fan", index),
+                            rpm,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Zones with a driver that also registered them with `hwmon` were read
+    /// already, under the zone type as the chip name. So were zones of a kind
+    /// `hwmon` covers under another name, e.g. `x86_pkg_temp` next to
+    /// `coretemp`.
+    fn read_thermal_zones(&self, readings: &mut SensorReadings) {
+        let from_hwmon = readings.temperatures.len();
+
+        for zone in sorted_entries(&self.root.join("class/thermal")) {
+            let is_zone = zone
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("thermal_zone"));
+            if !is_zone {
+                continue;
+            }
+
+            let Ok(kind) = read_trimmed(&zone.join("type")) else {
+                continue;
+            };
+            let sensor_kind = SensorKind::from_chip(&kind);
+            if readings.temperatures[..from_hwmon]
+                .iter()
+                .any(|temperature| {
+                    temperature.chip == kind
+                        || (sensor_kind != SensorKind::Other && temperature.kind == sensor_kind)
+                })
+            {
+                continue;
+            }
+
+            if let Some(celsius) = read_celsius(&zone.join("temp")) {
+                readings.temperatures.push(Temperature {
+                    kind: sensor_kind,
+                    chip: kind,
+                    label: "thermal zone".to_owned(),
+                    celsius,
+                });
+            }
+        }
+    }
+}
+
+pub async fn read(sensors: Sensors) -> SensorReadings {
+    tokio::task::spawn_blocking(move || sensors.read())
+        .await
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::testing::FakeRoot;
+
+    fn temperature(kind: SensorKind, celsius: f64) -> Temperature {
+        Temperature {
+            chip: kind.to_string(),
+            label: String::new(),
+            kind,
+            celsius,
+        }
+    }
+
+    #[test]
+    fn reads_hwmon_and_the_zones_it_doesnt_cover() {
+        let root = FakeRoot::new("sensors");
+        root.write("class/hwmon/hwmon0/name", "coretemp\n");
+        root.write("class/hwmon/hwmon0/temp1_input", "52000\n");
+        root.write("class/hwmon/hwmon0/temp1_label", "Package id 0\n");
+        root.write("class/hwmon/hwmon1/name", "thinkpad\n");
+        root.write("class/hwmon/hwmon1/fan1_input", "2400\n");
+        // Also read through coretemp, under another name.
+        root.write("class/thermal/thermal_zone0/type", "x86_pkg_temp\n");
+        root.write("class/thermal/thermal_zone0/temp", "53000\n");
+        root.write("class/thermal/thermal_zone1/type", "acpitz\n");
+        root.write("class/thermal/thermal_zone1/temp", "45000\n");
+        root.write("class/thermal/thermal_zone2/type", "acpitz\n");
+        root.write("class/thermal/thermal_zone2/temp", "47000\n");
+        root.write("class/thermal/cooling_device0/type", "Processor\n");
+
+        let readings = Sensors::new(root.path()).read();
+
+        let temperatures: Vec<_> = readings
+            .temperatures
+            .iter()
+            .map(|t| (t.chip.as_str(), t.label.as_str(), t.kind, t.celsius))
+            .collect();
+        assert_eq!(
+            temperatures,
+            [
+                ("coretemp", "Package id 0", SensorKind::Cpu, 52.0),
+                ("acpitz", "thermal zone", SensorKind::Other, 45.0),
+                ("acpitz", "thermal zone", SensorKind::Other, 47.0),
+            ]
+        );
+        assert_eq!(readings.loudest_fan().map(|fan| fan.rpm), Some(2400));
+    }
+
+    #[test]
+    fn alerts_stay_up_until_cooled_down() {
+        let alerts = ThermalAlerts::default();
+        let readings = |celsius| SensorReadings {
+            temperatures: vec![temperature(SensorKind::Cpu, celsius)],
+            fans: Vec::new(),
+        };
+
+        assert!(alerts.check(&readings(89.0), &[]).is_empty());
+        let raised = alerts.check(&readings(91.0), &[]);
+        assert_eq!(raised.len(), 1);
+        assert_eq!(alerts.check(&readings(87.0), &raised).len(), 1);
+        assert!(alerts.check(&readings(84.0), &raised).is_empty());
+    }
+}
@@ -0,0 +1,718 @@
+use iced::futures::{self, FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::PathBuf,
+    time::Duration,
+};
+
+/// CPU, memory and load from `/proc`. Everything is read relative to `root`,
+/// so a directory laid out the same way can stand in for it.
+#[derive(Debug, Clone)]
+pub struct SystemMonitor {
+    root: PathBuf,
+}
+
+/// Read from `system-monitor.json` in the config directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MonitorConfig {
+    /// Time between samples, in milliseconds.
+    pub interval: u64,
+    /// Samples kept for the sparklines.
+    pub history: usize,
+}
+
+impl MonitorConfig {
+    /// Sampling reads every process, so anything faster would keep a core
+    /// busy with it.
+    const MIN_INTERVAL: u64 = 250;
+
+    /// Raises an interval set too short in the config file.
+    pub fn clamped(self) -> Self {
+        Self {
+            interval: self.interval.max(Self::MIN_INTERVAL),
+            ..self
+        }
+    }
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self {
+            interval: 2_000,
+            history: 60,
+        }
+    }
+}
+
+/// Jiffies a CPU spent so far, from one `cpu` line of `/proc/stat`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct CpuTimes {
+    busy: u64,
+    total: u64,
+}
+
+impl CpuTimes {
+    /// `user nice system idle iowait irq softirq steal`, guest time being
+    /// counted in `user` already. Waiting on I/O is idling all the same.
+    fn parse(fields: &str) -> Option<Self> {
+        let times: Vec<u64> = fields
+            .split_whitespace()
+            .take(8)
+            .map(|field| field.parse().ok())
+            .collect::<Option<_>>()?;
+        if times.len() < 5 {
+            return None;
+        }
+
+        let total = times.iter().sum();
+        let idle = times[3] + times[4];
+
+        Some(Self {
+            busy: total - idle,
+            total,
+        })
+    }
+
+    /// Fraction of the time busy since `previous`.
+    fn usage_since(self, previous: CpuTimes) -> f64 {
+        let total = self.total.saturating_sub(previous.total);
+        if total == 0 {
+            return 0.0;
+        }
+
+        self.busy.saturating_sub(previous.busy) as f64 / total as f64
+    }
+}
+
+/// Every CPU together first, then each core.
+fn parse_stat(stat: &str) -> Vec<CpuTimes> {
+    stat.lines()
+        .filter_map(|line| line.strip_prefix("cpu"))
+        .filter_map(|line| {
+            let (_, fields) = line.split_once(char::is_whitespace)?;
+            CpuTimes::parse(fields)
+        })
+        .collect()
+}
+
+/// Kibibytes, as `/proc/meminfo` gives them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Memory {
+    pub used: u64,
+    pub total: u64,
+}
+
+impl Memory {
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.used as f64 / self.total as f64
+        }
+    }
+}
+
+/// Memory and swap. What the kernel considers available includes caches it
+/// can drop, so it's what counts as free.
+fn parse_meminfo(meminfo: &str) -> (Memory, Memory) {
+    let value = |key: &str| {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .and_then(|value| value.split_whitespace().next()?.parse::<u64>().ok())
+            .unwrap_or_default()
+    };
+
+    let memory = Memory {
+        used: value("MemTotal").saturating_sub(value("MemAvailable")),
+        total: value("MemTotal"),
+    };
+    let swap = Memory {
+        used: value("SwapTotal").saturating_sub(value("SwapFree")),
+        total: value("SwapTotal"),
+    };
+
+    (memory, swap)
+}
+
+/// Over the last 1, 5 and 15 minutes.
+fn parse_loadavg(loadavg: &str) -> [f64; 3] {
+    let mut load = [0.0; 3];
+    for (average, field) in load.iter_mut().zip(loadavg.split_whitespace()) {
+        *average = field.parse().unwrap_or_default();
+    }
+
+    load
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SystemStats {
+    /// Every CPU together, from 0 to 1.
+    pub cpu: f64,
+    pub cores: Vec<f64>,
+    pub memory: Memory,
+    pub swap: Memory,
+    pub load: [f64; 3],
+    /// Oldest first, from 0 to 1.
+    pub cpu_history: Vec<f64>,
+    pub memory_history: Vec<f64>,
+}
+
+struct Sample {
+    cpus: Vec<CpuTimes>,
+    memory: Memory,
+    swap: Memory,
+    load: [f64; 3],
+}
+
+impl SystemMonitor {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn system() -> Self {
+        Self::new("/proc")
+    }
+
+    fn sample(&self) -> io::Result<Sample> {
+        let cpus = parse_stat(&fs::read_to_string(self.root.join("stat"))?);
+        let (memory, swap) = parse_meminfo(&fs::read_to_string(self.root.join("meminfo"))?);
+        let load = parse_loadavg(&fs::read_to_string(self.root.join("loadavg"))?);
+
+        Ok(Sample {
+            cpus,
+            memory,
+            swap,
+            load,
+        })
+    }
+}
+
+/// Usage is over the time between two samples, so the first one only
+/// shows up after `config.interval`.
+struct Sampler {
+    monitor: SystemMonitor,
+    config: MonitorConfig,
+    previous: Vec<CpuTimes>,
+    cpu_history: VecDeque<f64>,
+    memory_history: VecDeque<f64>,
+}
+
+impl Sampler {
+    fn push(history: &mut VecDeque<f64>, limit: usize, value: f64) {
+        history.push_back(value);
+        while history.len() > limit {
+            history.pop_front();
+        }
+    }
+
+    fn next(&mut self, sample: Sample) -> SystemStats {
+        // A core brought online since has nothing to compare with.
+        let mut usage = sample.cpus.iter().enumerate().map(|(index, current)| {
+            let previous = self.previous.get(index).copied().unwrap_or_default();
+            current.usage_since(previous)
+        });
+        let cpu = usage.next().unwrap_or_default();
+        let cores = usage.collect();
+        self.previous = sample.cpus;
+
+        Self::push(&mut self.cpu_history, self.config.history, cpu);
+        Self::push(
+            &mut self.memory_history,
+            self.config.history,
+            sample.memory.fraction(),
+        );
+
+        SystemStats {
+            cpu,
+            cores,
+            memory: sample.memory,
+            swap: sample.swap,
+            load: sample.load,
+            cpu_history: self.cpu_history.iter().copied().collect(),
+            memory_history: self.memory_history.iter().copied().collect(),
+        }
+    }
+}
+
+async fn sample(monitor: SystemMonitor) -> io::Result<Sample> {
+    tokio::task::spawn_blocking(move || monitor.sample())
+        .await
+        .map_err(io::Error::other)?
+}
+
+/// Samples `monitor` every `config.interval` until a read fails.
+pub async fn event_stream(
+    monitor: SystemMonitor,
+    config: MonitorConfig,
+) -> io::Result<impl futures::Stream<Item = SystemStats>> {
+    let first = sample(monitor.clone()).await?;
+    let sampler = Sampler {
+        monitor,
+        config,
+        previous: first.cpus,
+        cpu_history: VecDeque::with_capacity(config.history),
+        memory_history: VecDeque::with_capacity(config.history),
+    };
+
+    Ok(futures::stream::unfold(sampler, |mut sampler| async move {
+        tokio::time::sleep(Duration::from_millis(sampler.config.interval)).await;
+
+        match sample(sampler.monitor.clone()).await {
+            Ok(sample) => Some((sampler.next(sample), sampler)),
+            Err(err) => {
+                eprintln!("Couldn't sample system stats: {err}");
+                None
+            }
+        }
+    }))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProcessSort {
+    #[default]
+    Cpu,
+    Memory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcessSignal {
+    /// `SIGTERM`, asking it to quit.
+    Terminate,
+    /// `SIGKILL`, which it can't refuse.
+    Kill,
+}
+
+impl ProcessSignal {
+    fn number(self) -> libc::c_int {
+        match self {
+            ProcessSignal::Terminate => libc::SIGTERM,
+            ProcessSignal::Kill => libc::SIGKILL,
+        }
+    }
+}
+
+impl std::fmt::Display for ProcessSignal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProcessSignal::Terminate => write!(f, "Terminate"),
+            ProcessSignal::Kill => write!(f, "Kill"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Process {
+    pub pid: i32,
+    pub name: String,
+    /// Like `top`, 1 being one core fully busy.
+    pub cpu: f64,
+    /// Resident set, in kibibytes.
+    pub memory: u64,
+    /// Jiffies after boot it started at, telling it apart from a later
+    /// process that got the same PID.
+    start_time: u64,
+}
+
+/// What `/proc/<pid>/stat` has for us: CPU time so far and when it started.
+/// The name in there is cut short and may hold anything, parentheses and
+/// spaces included, so everything is read after the last `)`.
+fn parse_process_stat(stat: &str) -> Option<(u64, u64)> {
+    let (_, fields) = stat.rsplit_once(')')?;
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+
+    // Numbered from `state`, the third field, on.
+    let field = |number: usize| fields.get(number - 3)?.parse::<u64>().ok();
+    let (utime, stime, start_time) = (field(14)?, field(15)?, field(22)?);
+
+    Some((utime + stime, start_time))
+}
+
+/// Name and resident memory from `/proc/<pid>/status`. Kernel threads have
+/// no memory of their own.
+fn parse_process_status(status: &str) -> Option<(String, u64)> {
+    let value = |key: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .map(str::trim)
+    };
+
+    let name = value("Name")?.to_owned();
+    let memory = value("VmRSS")
+        .and_then(|memory| memory.split_whitespace().next()?.parse().ok())
+        .unwrap_or_default();
+
+    Some((name, memory))
+}
+
+/// Sorted with the heaviest first.
+pub fn sort_processes(processes: &mut [Process], by: ProcessSort) {
+    match by {
+        ProcessSort::Cpu => processes.sort_by(|a, b| b.cpu.total_cmp(&a.cpu)),
+        ProcessSort::Memory => processes.sort_by_key(|process| std::cmp::Reverse(process.memory)),
+    }
+}
+
+/// Every process along with its CPU time so far, which only means
+/// something compared to an earlier reading.
+type ProcessTimes = Vec<(Process, u64)>;
+
+impl SystemMonitor {
+    fn processes(&self) -> io::Result<ProcessTimes> {
+        let mut processes = Vec::new();
+
+        for entry in fs::read_dir(&self.root)?.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+                continue;
+            };
+
+            // Processes exit while we're reading them.
+            let (Ok(stat), Ok(status)) = (
+                fs::read_to_string(entry.path().join("stat")),
+                fs::read_to_string(entry.path().join("status")),
+            ) else {
+                continue;
+            };
+            let (Some((time, start_time)), Some((name, memory))) =
+                (parse_process_stat(&stat), parse_process_status(&status))
+            else {
+                continue;
+            };
+
+            let process = Process {
+                pid,
+                name,
+                cpu: 0.0,
+                memory,
+                start_time,
+            };
+            processes.push((process, time));
+        }
+
+        Ok(processes)
+    }
+
+    /// Signals `process`, unless its PID now belongs to another one.
+    fn signal(&self, process: &Process, signal: ProcessSignal) -> io::Result<()> {
+        let stat = fs::read_to_string(self.root.join(process.pid.to_string()).join("stat"))?;
+        if parse_process_stat(&stat).map(|(_, start_time)| start_time) != Some(process.start_time) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} already exited", process.name),
+            ));
+        }
+
+        // SAFETY: `kill` has no memory safety requirements.
+        if unsafe { libc::kill(process.pid, signal.number()) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// Usage is over the time between two samples, so the first list only
+/// shows up after `config.interval`.
+struct ProcessSampler {
+    monitor: SystemMonitor,
+    config: MonitorConfig,
+    /// Every CPU together, then each core.
+    previous_cpus: Vec<CpuTimes>,
+    previous_times: HashMap<i32, u64>,
+}
+
+impl ProcessSampler {
+    fn next(&mut self, cpus: Vec<CpuTimes>, processes: ProcessTimes) -> Vec<Process> {
+        let elapsed = match (cpus.first(), self.previous_cpus.first()) {
+            (Some(current), Some(previous)) => current.total.saturating_sub(previous.total),
+            _ => 0,
+        };
+        // The total counts every core, a process is measured against one.
+        let cores = cpus.len().saturating_sub(1).max(1) as f64;
+        let elapsed = elapsed as f64 / cores;
+
+        let mut times = HashMap::with_capacity(processes.len());
+        let processes = processes
+            .into_iter()
+            .map(|(mut process, time)| {
+                // A process started since is measured from zero.
+                let previous = self.previous_times.get(&process.pid).copied().unwrap_or(0);
+                if elapsed > 0.0 {
+                    process.cpu = time.saturating_sub(previous) as f64 / elapsed;
+                }
+                times.insert(process.pid, time);
+
+                process
+            })
+            .collect();
+
+        self.previous_cpus = cpus;
+        self.previous_times = times;
+
+        processes
+    }
+}
+
+fn sample_processes(monitor: &SystemMonitor) -> io::Result<(Vec<CpuTimes>, ProcessTimes)> {
+    let cpus = parse_stat(&fs::read_to_string(monitor.root.join("stat"))?);
+    let processes = monitor.processes()?;
+
+    Ok((cpus, processes))
+}
+
+/// Lists every process under `monitor` every `config.interval`, unsorted,
+/// until a read fails.
+pub async fn process_stream(
+    monitor: SystemMonitor,
+    config: MonitorConfig,
+) -> io::Result<impl futures::Stream<Item = Vec<Process>>> {
+    let first = monitor.clone();
+    let (cpus, processes) = tokio::task::spawn_blocking(move || sample_processes(&first))
+        .await
+        .map_err(io::Error::other)??;
+
+    let sampler = ProcessSampler {
+        monitor,
+        config,
+        previous_cpus: cpus,
+        previous_times: processes
+            .into_iter()
+            .map(|(process, time)| (process.pid, time))
+            .collect(),
+    };
+
+    Ok(futures::stream::unfold(sampler, |mut sampler| async move {
+        tokio::time::sleep(Duration::from_millis(sampler.config.interval)).await;
+
+        let monitor = sampler.monitor.clone();
+        let result = tokio::task::spawn_blocking(move || sample_processes(&monitor))
+            .await
+            .map_err(io::Error::other);
+
+        match result {
+            Ok(Ok((cpus, processes))) => Some((sampler.next(cpus, processes), sampler)),
+            Ok(Err(err)) | Err(err) => {
+                eprintln!("Couldn't list processes: {err}");
+                None
+            }
+        }
+    }))
+}
+
+pub async fn send_signal(process: Process, signal: ProcessSignal) -> Result<(), String> {
+    let result = tokio::task::spawn_blocking(move || {
+        SystemMonitor::system()
+            .signal(&process, signal)
+            .map_err(|err| {
+                let verb = signal.to_string().to_lowercase();
+                format!("Couldn't {verb} {}: {err}", process.name)
+            })
+    })
+    .await;
+
+    result
+        .map_err(|err| err.to_string())
+        .and_then(|result| result)
+        .inspect_err(|err| eprintln!("{err}"))
+}
+
+/// The config is part of the subscription's identity, so changing it
+/// starts sampling over.
+pub fn subscription<I>(id: I, config: MonitorConfig) -> iced::Subscription<SystemStats>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("system-monitor", id, config),
+        async move {
+            match event_stream(SystemMonitor::system(), config).await {
+                Ok(stream) => stream.boxed(),
+                Err(err) => {
+                    eprintln!("Couldn't read system stats: {err}");
+                    futures::stream::pending().boxed()
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+pub fn process_subscription<I>(id: I, config: MonitorConfig) -> iced::Subscription<Vec<Process>>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("processes", id, config),
+        async move {
+            match process_stream(SystemMonitor::system(), config).await {
+                Ok(stream) => stream.boxed(),
+                Err(err) => {
+                    eprintln!("Couldn't list processes: {err}");
+                    futures::stream::pending().boxed()
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding::testing::{self, FakeRoot};
+
+    const STAT: &str = "\
+cpu  100 0 100 700 100 0 0 0 0 0
+cpu0 50 0 50 350 50 0 0 0 0 0
+cpu1 50 0 50 350 50 0 0 0 0 0
+intr 12345 0 0
+ctxt 6789
+";
+
+    const MEMINFO: &str = "\
+MemTotal:       16000000 kB
+MemFree:         2000000 kB
+MemAvailable:    6000000 kB
+SwapTotal:       4000000 kB
+SwapFree:        3000000 kB
+";
+
+    fn process_stat(pid: i32, name: &str, time: (u64, u64), start_time: u64) -> String {
+        let (utime, stime) = time;
+        format!(
+            "{pid} ({name}) S 1 2 3 4 5 6 7 8 9 10 {utime} {stime} 0 0 20 0 1 0 {start_time} 0 0\n"
+        )
+    }
+
+    fn fake_proc() -> FakeRoot {
+        let root = FakeRoot::new("proc");
+        root.write("stat", STAT);
+        root.write("meminfo", MEMINFO);
+        root.write("loadavg", "0.50 0.25 0.10 1/234 5678\n");
+        root.write("42/stat", &process_stat(42, "editor", (100, 50), 4242));
+        root.write("42/status", "Name:\teditor\nVmRSS:\t  2048 kB\n");
+        // Kernel threads have no VmRSS.
+        root.write("2/stat", &process_stat(2, "kthreadd", (0, 0), 1));
+        root.write("2/status", "Name:\tkthreadd\n");
+        // Not a process.
+        root.write("self-test/stat", "garbage");
+
+        root
+    }
+
+    #[test]
+    fn stat_lists_every_cpu_then_each_core() {
+        let cpus = parse_stat(STAT);
+
+        assert_eq!(
+            cpus,
+            vec![
+                CpuTimes {
+                    busy: 200,
+                    total: 1000
+                },
+                CpuTimes {
+                    busy: 100,
+                    total: 500
+                },
+                CpuTimes {
+                    busy: 100,
+                    total: 500
+                },
+            ]
+        );
+        assert_eq!(cpus[0].usage_since(CpuTimes::default()), 0.2);
+    }
+
+    #[test]
+    fn meminfo_counts_available_memory_as_free() {
+        let (memory, swap) = parse_meminfo(MEMINFO);
+
+        assert_eq!(
+            memory,
+            Memory {
+                used: 10_000_000,
+                total: 16_000_000
+            }
+        );
+        assert_eq!(
+            swap,
+            Memory {
+                used: 1_000_000,
+                total: 4_000_000
+            }
+        );
+        assert_eq!(parse_meminfo(""), (Memory::default(), Memory::default()));
+    }
+
+    #[test]
+    fn process_stat_reads_past_odd_names() {
+        let stat = process_stat(7, "a) (b c", (30, 12), 999);
+
+        assert_eq!(parse_process_stat(&stat), Some((42, 999)));
+        assert_eq!(parse_process_stat("7 (short) S 1 2"), None);
+    }
+
+    #[test]
+    fn samples_the_fake_root() {
+        let root = fake_proc();
+        let sample = SystemMonitor::new(root.path()).sample().unwrap();
+
+        assert_eq!(sample.cpus.len(), 3);
+        assert_eq!(sample.memory.total, 16_000_000);
+        assert_eq!(sample.load, [0.5, 0.25, 0.1]);
+    }
+
+    #[test]
+    fn lists_the_processes_of_the_fake_root() {
+        let root = fake_proc();
+        let mut processes = SystemMonitor::new(root.path()).processes().unwrap();
+        processes.sort_by_key(|(process, _)| process.pid);
+
+        let summary: Vec<_> = processes
+            .iter()
+            .map(|(process, time)| (process.pid, process.name.as_str(), process.memory, *time))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![(2, "kthreadd", 0, 0), (42, "editor", 2048, 150)]
+        );
+    }
+
+    #[tokio::test]
+    async fn measures_usage_between_samples() {
+        let root = fake_proc();
+        let config = MonitorConfig {
+            interval: 10,
+            history: 2,
+        };
+        let mut stream = Box::pin(
+            event_stream(SystemMonitor::new(root.path()), config)
+                .await
+                .unwrap(),
+        );
+
+        // Half of the next 1000 jiffies busy.
+        root.write("stat", "cpu  350 0 350 1000 300 0 0 0 0 0\n");
+        let stats = testing::next(&mut stream).await;
+
+        assert_eq!(stats.cpu, 0.5);
+        assert_eq!(stats.cpu_history, vec![0.5]);
+        assert_eq!(stats.memory_history, vec![10.0 / 16.0]);
+    }
+
+    #[test]
+    fn short_intervals_are_raised() {
+        let config = MonitorConfig {
+            interval: 10,
+            history: 60,
+        };
+
+        assert_eq!(config.clamped().interval, 250);
+        assert_eq!(MonitorConfig::default().clamped(), MonitorConfig::default());
+    }
+}
@@ -0,0 +1,103 @@
+use iced::futures::{Stream, StreamExt};
+use std::{
+    io::{BufRead, BufReader},
+    path::PathBuf,
+    process::{Child, Command, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+/// A `dbus-daemon` of our own, so mock services never touch the real buses.
+/// It's killed when dropped.
+pub struct PrivateBus {
+    daemon: Child,
+    address: String,
+}
+
+impl PrivateBus {
+    pub fn start() -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("dbus-daemon should be installed");
+
+        let mut address = String::new();
+        let stdout = daemon.stdout.take().expect("stdout is piped");
+        BufReader::new(stdout)
+            .read_line(&mut address)
+            .expect("dbus-daemon should print its address");
+
+        Self {
+            daemon,
+            address: address.trim().to_owned(),
+        }
+    }
+
+    pub async fn connect(&self) -> zbus::Connection {
+        zbus::connection::Builder::address(self.address.as_str())
+            .expect("the address should be valid")
+            .build()
+            .await
+            .expect("the bus should accept connections")
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// An empty directory to lay out a fake sysfs or procfs in, removed when
+/// dropped.
+pub struct FakeRoot(PathBuf);
+
+impl FakeRoot {
+    pub fn new(name: &str) -> Self {
+        // Tests run in parallel, so every root gets a name of its own.
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("wawi-{name}-{}-{count}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).expect("the temporary directory should be writable");
+
+        Self(path)
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.0
+    }
+
+    /// Writes `contents` to `relative`, creating the directories on the way.
+    pub fn write(&self, relative: &str, contents: &str) {
+        let path = self.0.join(relative);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).expect("the directories should be creatable");
+        }
+        std::fs::write(path, contents).expect("the file should be writable");
+    }
+
+    pub fn read(&self, relative: &str) -> String {
+        std::fs::read_to_string(self.0.join(relative)).expect("the file should exist")
+    }
+}
+
+impl Drop for FakeRoot {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// The next item of `stream`, failing the test if it takes too long.
+pub async fn next<S>(stream: &mut S) -> S::Item
+where
+    S: Stream + Unpin,
+{
+    tokio::time::timeout(Duration::from_secs(5), stream.next())
+        .await
+        .expect("the stream should yield in time")
+        .expect("the stream shouldn't end")
+}
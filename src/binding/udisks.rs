@@ -0,0 +1,353 @@
+use iced::futures::{self, FutureExt, StreamExt};
+use std::collections::HashMap;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+const UDISKS: &str = "org.freedesktop.UDisks2";
+const DRIVE_INTERFACE: &str = "org.freedesktop.UDisks2.Drive";
+const BLOCK_INTERFACE: &str = "org.freedesktop.UDisks2.Block";
+const FILESYSTEM_INTERFACE: &str = "org.freedesktop.UDisks2.Filesystem";
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UDisks2.Filesystem",
+    default_service = "org.freedesktop.UDisks2"
+)]
+trait Filesystem {
+    /// Returns where it got mounted.
+    fn mount(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<String>;
+
+    fn unmount(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+}
+
+#[zbus::proxy(
+    interface = "org.freedesktop.UDisks2.Drive",
+    default_service = "org.freedesktop.UDisks2"
+)]
+trait Drive {
+    fn eject(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+    fn power_off(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn ejectable(&self) -> zbus::Result<bool>;
+}
+
+/// A filesystem on a removable drive.
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub path: OwnedObjectPath,
+    pub label: String,
+    /// Bytes.
+    pub size: u64,
+    pub mount_point: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RemovableDrive {
+    pub path: OwnedObjectPath,
+    /// Vendor and model.
+    pub name: String,
+    /// Whether it can be ejected or powered off once unmounted.
+    pub ejectable: bool,
+    pub volumes: Vec<Volume>,
+}
+
+#[derive(Debug, Clone)]
+pub enum StorageInfo {
+    NotAvailable,
+    Available { drives: Vec<RemovableDrive> },
+}
+
+type Properties = HashMap<String, OwnedValue>;
+
+fn string_property(properties: &Properties, key: &str) -> Option<String> {
+    properties
+        .get(key)?
+        .downcast_ref::<String>()
+        .ok()
+        .filter(|value| !value.is_empty())
+}
+
+fn bool_property(properties: &Properties, key: &str) -> bool {
+    properties
+        .get(key)
+        .and_then(|value| value.downcast_ref::<bool>().ok())
+        .unwrap_or_default()
+}
+
+fn u64_property(properties: &Properties, key: &str) -> u64 {
+    properties
+        .get(key)
+        .and_then(|value| value.downcast_ref::<u64>().ok())
+        .unwrap_or_default()
+}
+
+/// Byte strings, NUL terminated, as UDisks gives paths.
+fn bytestring(value: OwnedValue) -> Option<String> {
+    let bytes = Vec::<u8>::try_from(value).ok()?;
+    let bytes = bytes.strip_suffix(&[0]).unwrap_or(&bytes);
+
+    Some(String::from_utf8_lossy(bytes).into_owned())
+}
+
+fn object_path_property(properties: &Properties, key: &str) -> Option<OwnedObjectPath> {
+    let value = properties.get(key)?.try_clone().ok()?;
+    OwnedObjectPath::try_from(value).ok()
+}
+
+/// The first place it's mounted at, if any.
+fn mount_point(filesystem: &Properties) -> Option<String> {
+    let mount_points = filesystem.get("MountPoints")?.try_clone().ok()?;
+    let mount_points = Vec::<OwnedValue>::try_from(mount_points).ok()?;
+
+    mount_points.into_iter().find_map(bytestring)
+}
+
+async fn object_manager(
+    conn: &zbus::Connection,
+) -> zbus::Result<zbus::fdo::ObjectManagerProxy<'static>> {
+    zbus::fdo::ObjectManagerProxy::builder(conn)
+        .destination(UDISKS)?
+        .path("/org/freedesktop/UDisks2")?
+        .build()
+        .await
+}
+
+/// Drives with removable media, each with the filesystems on it. Drives
+/// without any, e.g. a card reader with no card in it, are left out.
+async fn storage_info(conn: &zbus::Connection) -> zbus::Result<StorageInfo> {
+    let objects = object_manager(conn).await?.get_managed_objects().await?;
+
+    let mut drives: Vec<RemovableDrive> = objects
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            let drive = interfaces.get(DRIVE_INTERFACE)?;
+            let is_removable = bool_property(drive, "Removable")
+                || string_property(drive, "ConnectionBus").as_deref() == Some("usb");
+            if !is_removable {
+                return None;
+            }
+
+            let name = [
+                string_property(drive, "Vendor"),
+                string_property(drive, "Model"),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<String>>()
+            .join(" ");
+
+            Some(RemovableDrive {
+                path: path.clone(),
+                name,
+                ejectable: bool_property(drive, "Ejectable") || bool_property(drive, "CanPowerOff"),
+                volumes: Vec::new(),
+            })
+        })
+        .collect();
+
+    for (path, interfaces) in &objects {
+        let (Some(block), Some(filesystem)) = (
+            interfaces.get(BLOCK_INTERFACE),
+            interfaces.get(FILESYSTEM_INTERFACE),
+        ) else {
+            continue;
+        };
+        // Set by udev rules, e.g. for recovery partitions.
+        if bool_property(block, "HintIgnore") {
+            continue;
+        }
+        let Some(drive_path) = object_path_property(block, "Drive") else {
+            continue;
+        };
+        let Some(drive) = drives.iter_mut().find(|drive| drive.path == drive_path) else {
+            continue;
+        };
+
+        let label = string_property(block, "IdLabel")
+            .or_else(|| string_property(block, "HintName"))
+            .or_else(|| {
+                block
+                    .get("PreferredDevice")
+                    .and_then(|device| device.try_clone().ok())
+                    .and_then(bytestring)
+            })
+            .unwrap_or_default();
+
+        drive.volumes.push(Volume {
+            path: path.clone(),
+            label,
+            size: u64_property(block, "Size"),
+            mount_point: mount_point(filesystem),
+        });
+    }
+
+    drives.retain(|drive| !drive.volumes.is_empty());
+    drives.sort_by(|a, b| a.name.cmp(&b.name));
+    for drive in &mut drives {
+        drive
+            .volumes
+            .sort_by(|a, b| a.path.as_str().cmp(b.path.as_str()));
+    }
+
+    Ok(StorageInfo::Available { drives })
+}
+
+async fn filesystem(
+    conn: &zbus::Connection,
+    path: OwnedObjectPath,
+) -> zbus::Result<FilesystemProxy<'static>> {
+    FilesystemProxy::builder(conn)
+        .path(path)?
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await
+}
+
+/// Mounts under `/run/media/$USER`, asking for a password through polkit if
+/// need be.
+pub async fn mount(volume: Volume) -> Result<(), String> {
+    let result = async {
+        let conn = zbus::Connection::system().await?;
+        filesystem(&conn, volume.path)
+            .await?
+            .mount(HashMap::new())
+            .await
+    }
+    .await;
+
+    result.map(|_| ()).map_err(|err| {
+        eprintln!("Couldn't mount {}: {err}", volume.label);
+        err.to_string()
+    })
+}
+
+pub async fn unmount(volume: Volume) -> Result<(), String> {
+    let result = async {
+        let conn = zbus::Connection::system().await?;
+        filesystem(&conn, volume.path)
+            .await?
+            .unmount(HashMap::new())
+            .await
+    }
+    .await;
+
+    result.map_err(|err| {
+        eprintln!("Couldn't unmount {}: {err}", volume.label);
+        err.to_string()
+    })
+}
+
+/// Unmounts everything on the drive, then ejects the media. USB sticks
+/// have no media to eject, so they're powered off instead, which is what
+/// makes them safe to pull out. A failed eject is reported as is, powering
+/// off a drive that was meant to keep spinning would be a surprise.
+pub async fn eject(drive: RemovableDrive) -> Result<(), String> {
+    let result = async {
+        let conn = zbus::Connection::system().await?;
+
+        for volume in &drive.volumes {
+            if volume.mount_point.is_some() {
+                filesystem(&conn, volume.path.clone())
+                    .await?
+                    .unmount(HashMap::new())
+                    .await?;
+            }
+        }
+
+        let proxy = DriveProxy::builder(&conn)
+            .path(drive.path.clone())?
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await?;
+
+        if proxy.ejectable().await? {
+            proxy.eject(HashMap::new()).await
+        } else {
+            proxy.power_off(HashMap::new()).await
+        }
+    }
+    .await;
+
+    result.map_err(|err| {
+        eprintln!("Couldn't eject {}: {err}", drive.name);
+        err.to_string()
+    })
+}
+
+/// Follows UDisks on `conn`, with signals read from `signal_conn` so a
+/// backed up queue can't stall the snapshots. Both are parameters so a
+/// private bus can stand in for the system one.
+pub async fn event_stream(
+    conn: zbus::Connection,
+    signal_conn: zbus::Connection,
+) -> zbus::Result<impl futures::Stream<Item = StorageInfo>> {
+    // Drives and filesystems coming and going, i.e. hotplug.
+    let interfaces_added = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(UDISKS)?
+        .interface("org.freedesktop.DBus.ObjectManager")?
+        .member("InterfacesAdded")?
+        .build();
+    let interfaces_removed = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(UDISKS)?
+        .interface("org.freedesktop.DBus.ObjectManager")?
+        .member("InterfacesRemoved")?
+        .build();
+    // Filesystems getting mounted and unmounted, by us or anyone else.
+    let properties_changed = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .sender(UDISKS)?
+        .interface("org.freedesktop.DBus.Properties")?
+        .member("PropertiesChanged")?
+        .build();
+    let signals = futures::stream_select!(
+        zbus::MessageStream::for_match_rule(interfaces_added, &signal_conn, None).await?,
+        zbus::MessageStream::for_match_rule(interfaces_removed, &signal_conn, None).await?,
+        zbus::MessageStream::for_match_rule(properties_changed, &signal_conn, None).await?,
+    );
+
+    let initial = storage_info(&conn).await?;
+
+    // Plugging a drive in announces every partition on it at once. A failed
+    // snapshot keeps the last state, so drives don't vanish for a moment,
+    // pending actions and all.
+    let updates = signals.ready_chunks(64).filter_map(move |_| {
+        let conn = conn.clone();
+        async move {
+            storage_info(&conn)
+                .await
+                .inspect_err(|err| eprintln!("Couldn't read the storage state: {err}"))
+                .ok()
+        }
+    });
+
+    Ok(futures::stream::once(async { initial }).chain(updates))
+}
+
+pub fn subscription<I>(id: I) -> iced::Subscription<StorageInfo>
+where
+    I: 'static + std::hash::Hash,
+{
+    iced::subscription::run_with_id(
+        ("udisks", id),
+        async move {
+            let stream = async {
+                let conn = zbus::Connection::system().await?;
+                let signal_conn = zbus::Connection::system().await?;
+                event_stream(conn, signal_conn).await
+            };
+
+            match stream.await {
+                Ok(stream) => stream.boxed(),
+                Err(err) => {
+                    eprintln!("Couldn't follow UDisks: {err}");
+                    futures::stream::once(async { StorageInfo::NotAvailable })
+                        .chain(futures::stream::pending())
+                        .boxed()
+                }
+            }
+        }
+        .flatten_stream(),
+    )
+}
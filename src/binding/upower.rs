@@ -209,7 +209,7 @@ where
     I: 'static + std::hash::Hash,
 {
     iced::subscription::run_with_id(
-        id,
+        ("upower", id),
         async move {
             match event_stream().await {
                 Ok(stream) => stream,
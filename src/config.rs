@@ -0,0 +1,59 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::PathBuf;
+
+fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(base.join("wawi"))
+}
+
+fn config_path(name: &str) -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(format!("{name}.json")))
+}
+
+/// Reads `$XDG_CONFIG_HOME/wawi/{name}.json`, falling back to [`Default`] if
+/// it doesn't exist or can't be parsed.
+pub fn load<T>(name: &str) -> T
+where
+    T: DeserializeOwned + Default,
+{
+    let Some(path) = config_path(name) else {
+        return T::default();
+    };
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return T::default();
+    };
+
+    serde_json::from_str(&contents).unwrap_or_else(|err| {
+        eprintln!("Couldn't parse {}: {err}", path.display());
+        T::default()
+    })
+}
+
+pub async fn save<T>(name: &str, value: T)
+where
+    T: Serialize,
+{
+    let (Some(dir), Some(path)) = (config_dir(), config_path(name)) else {
+        return;
+    };
+    let contents = match serde_json::to_string_pretty(&value) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("Couldn't serialize {name}: {err}");
+            return;
+        }
+    };
+
+    if let Err(err) = tokio::fs::create_dir_all(&dir).await {
+        eprintln!("Couldn't create {}: {err}", dir.display());
+        return;
+    }
+
+    if let Err(err) = tokio::fs::write(&path, contents).await {
+        eprintln!("Couldn't write {}: {err}", path.display());
+    }
+}
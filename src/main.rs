@@ -1,4 +1,5 @@
 mod binding;
+mod config;
 mod panel;
 mod styling;
 mod widget;
@@ -9,6 +10,26 @@ use iced_layershell::{
 };
 
 // fn main() -> Result<(), iced_layershell::Error> {
+//     let (panel_link, popup_link) = panel::popups::link();
+//
+//     // Each surface runs an event loop of its own.
+//     std::thread::spawn(move || {
+//         panel::popups::NotificationPopups::run(iced_layershell::settings::Settings {
+//             id: Some("notification_popups".to_string()),
+//             antialiasing: true,
+//             default_font: styling::font::SF_PRO,
+//             layer_settings: iced_layershell::settings::LayerShellSettings {
+//                 layer: Layer::Overlay,
+//                 anchor: Anchor::Right | Anchor::Top,
+//                 margins: (40 + 15, 10, 0, 0),
+//                 size: Some((400, 500)),
+//                 ..Default::default()
+//             },
+//             flags: popup_link,
+//             ..Default::default()
+//         })
+//     });
+//
 //     panel::ControlCenter::run(iced_layershell::settings::Settings {
 //         id: Some("control_center".to_string()),
 //         antialiasing: true,
@@ -20,6 +41,7 @@ use iced_layershell::{
 //             size: Some((475, 375)),
 //             ..Default::default()
 //         },
+//         flags: panel_link,
 //         ..Default::default()
 //     })
 // }
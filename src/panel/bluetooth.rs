@@ -0,0 +1,187 @@
+use super::{icon_path, Message, Page};
+use crate::binding::bluez::{BluetoothDevice, PairingKind, PairingRequest};
+use crate::styling;
+
+use iced::{
+    widget::{button, column, container, row, scrollable, svg, text},
+    Alignment, Element, Length,
+};
+use zbus::zvariant::OwnedObjectPath;
+
+/// `pending` is the device we're waiting to (dis)connect or pair, if any.
+pub fn view<'a>(
+    powered: bool,
+    devices: &'a [BluetoothDevice],
+    discovered: &'a [BluetoothDevice],
+    is_discovering: bool,
+    pending: Option<&'a OwnedObjectPath>,
+    error: Option<&'a str>,
+) -> Element<'a, Message, styling::theme::Theme> {
+    let icon = |icon_path: String| svg(svg::Handle::from_path(icon_path)).width(25).height(25);
+
+    let header = row![
+        button(icon(icon_path("chevron-left")))
+            .style(styling::style::Button::Icon)
+            .padding(0)
+            .on_press(Message::ShowPage(Page::Main)),
+        text("Bluetooth")
+            .font(styling::font::SF_PRO_BOLD)
+            .size(16)
+            .width(Length::Fill),
+        button(text(if powered { "On" } else { "Off" }))
+            .style(if powered {
+                styling::style::Button::Selected
+            } else {
+                styling::style::Button::Default
+            })
+            .on_press(Message::ToggleBluetooth),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    if !powered {
+        return container(column![header, text("Bluetooth is off")].spacing(20))
+            .width(Length::Fill)
+            .into();
+    }
+
+    let error: Element<Message, styling::theme::Theme> = match error {
+        Some(error) => text(format!("Couldn't connect: {error}")).size(12).into(),
+        None => column![].into(),
+    };
+
+    let device_button = |device: &'a BluetoothDevice, status: String, message| {
+        button(
+            row![
+                icon(icon_path("bluetooth")),
+                text(&device.name).width(Length::Fill),
+                text(status).size(12),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+        )
+        .style(if device.connected {
+            styling::style::Button::Selected
+        } else {
+            styling::style::Button::Default
+        })
+        .width(Length::Fill)
+        .on_press_maybe(pending.is_none().then_some(message))
+    };
+
+    let paired: Element<Message, styling::theme::Theme> = if devices.is_empty() {
+        text("No paired devices").into()
+    } else {
+        column(devices.iter().map(|device| {
+            let is_pending = pending.is_some_and(|path| *path == device.path);
+
+            let status = match device.battery {
+                _ if is_pending => "…".to_string(),
+                Some(battery) if device.connected => format!("{battery}%"),
+                _ if device.connected => "Connected".to_string(),
+                _ => String::new(),
+            };
+
+            device_button(
+                device,
+                status,
+                Message::SetBluetoothConnected(device.path.clone(), !device.connected),
+            )
+            .into()
+        }))
+        .spacing(5)
+        .into()
+    };
+
+    let discovery_header = row![
+        text("Other devices")
+            .font(styling::font::SF_PRO_BOLD)
+            .width(Length::Fill),
+        button(text(if is_discovering { "Stop" } else { "Scan" }))
+            .style(if is_discovering {
+                styling::style::Button::Selected
+            } else {
+                styling::style::Button::Default
+            })
+            .on_press(Message::ToggleBluetoothDiscovery),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let discovered = column(discovered.iter().map(|device| {
+        let is_pending = pending.is_some_and(|path| *path == device.path);
+
+        device_button(
+            device,
+            if is_pending { "Pairing…" } else { "" }.to_string(),
+            Message::PairBluetoothDevice(device.path.clone()),
+        )
+        .into()
+    }))
+    .spacing(5);
+
+    container(
+        column![
+            header,
+            error,
+            scrollable(column![paired, discovery_header, discovered].spacing(20))
+                .height(Length::Fill),
+        ]
+        .spacing(20),
+    )
+    .width(Length::Fill)
+    .into()
+}
+
+/// Shown on top of whatever page is open, since pairing requests can come at any time.
+pub fn pairing_dialog(request: &PairingRequest) -> Element<'_, Message, styling::theme::Theme> {
+    let (title, body, accept, reject) = match &request.kind {
+        PairingKind::Confirm(passkey) => (
+            format!("Pair with {}?", request.device),
+            format!("Make sure it shows the passkey {passkey:06}."),
+            "Pair",
+            Some("Cancel"),
+        ),
+        PairingKind::Authorize => (
+            format!("Allow {}?", request.device),
+            "It wants to connect to this computer.".to_string(),
+            "Allow",
+            Some("Deny"),
+        ),
+        PairingKind::DisplayPasskey(passkey) => (
+            format!("Pairing with {}", request.device),
+            format!("Type {passkey:06} on the device, then press Enter."),
+            "OK",
+            None,
+        ),
+        PairingKind::DisplayPinCode(pincode) => (
+            format!("Pairing with {}", request.device),
+            format!("Type {pincode} on the device, then press Enter."),
+            "OK",
+            None,
+        ),
+    };
+
+    let mut buttons = row![].spacing(10);
+    if let Some(reject) = reject {
+        buttons = buttons.push(button(text(reject)).on_press(Message::AnswerPairing(false)));
+    }
+    buttons = buttons.push(
+        button(text(accept))
+            .style(styling::style::Button::Selected)
+            .on_press(Message::AnswerPairing(true)),
+    );
+
+    container(
+        column![
+            text(title).font(styling::font::SF_PRO_BOLD),
+            text(body),
+            buttons,
+        ]
+        .spacing(10),
+    )
+    .style(styling::style::Container::Rounded)
+    .padding(15)
+    .width(Length::Fill)
+    .into()
+}
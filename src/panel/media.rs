@@ -0,0 +1,102 @@
+use super::{icon_path, Message};
+use crate::binding::mpris::{PlaybackStatus, PlayerAction, PlayerInfo};
+use crate::styling;
+
+use iced::{
+    widget::{button, column, container, image, row, slider, svg, text},
+    Alignment, Element, Length,
+};
+
+const ART_SIZE: u16 = 64;
+
+fn control_button<'a>(
+    content: impl Into<Element<'a, Message, styling::theme::Theme>>,
+    action: PlayerAction,
+    enabled: bool,
+) -> iced::widget::Button<'a, Message, styling::theme::Theme> {
+    button(content)
+        .style(styling::style::Button::Icon)
+        .padding(0)
+        .on_press_maybe(enabled.then_some(Message::ControlMediaPlayer(action)))
+}
+
+/// `players` is shown in the card footer when there's more than one to switch between.
+pub fn view(player: &PlayerInfo, players: usize) -> Element<'_, Message, styling::theme::Theme> {
+    let art: Element<Message, styling::theme::Theme> = match &player.art {
+        Some(path) => image(image::Handle::from_path(path))
+            .width(ART_SIZE)
+            .height(ART_SIZE)
+            .into(),
+        None => svg(svg::Handle::from_path(icon_path("play")))
+            .width(ART_SIZE)
+            .height(ART_SIZE)
+            .into(),
+    };
+
+    let play_pause = match player.status {
+        PlaybackStatus::Playing => "pause",
+        PlaybackStatus::Paused | PlaybackStatus::Stopped => "play",
+    };
+
+    let controls = row![
+        control_button(
+            text("«").size(20),
+            PlayerAction::Previous,
+            player.can_go_previous
+        ),
+        control_button(
+            svg(svg::Handle::from_path(icon_path(play_pause)))
+                .width(20)
+                .height(20),
+            PlayerAction::PlayPause,
+            true
+        ),
+        control_button(text("»").size(20), PlayerAction::Next, player.can_go_next),
+    ]
+    .spacing(15)
+    .align_items(Alignment::Center);
+
+    let length = (player.length / 1_000_000).max(0) as u32;
+    let position = ((player.position / 1_000_000).max(0) as u32).min(length);
+    let progress: Element<Message, styling::theme::Theme> = if player.can_seek && length > 0 {
+        slider(0..=length, position, Message::SeekMediaPlayer)
+            .on_release(Message::ReleaseMediaSeek)
+            .into()
+    } else {
+        column![].into()
+    };
+
+    let switcher: Element<Message, styling::theme::Theme> = if players > 1 {
+        button(text(format!("{} ({players} players)", player.identity)).size(12))
+            .style(styling::style::Button::Icon)
+            .padding(0)
+            .on_press(Message::NextMediaPlayer)
+            .into()
+    } else {
+        text(&player.identity).size(12).into()
+    };
+
+    container(
+        column![
+            row![
+                art,
+                column![
+                    text(&player.title).font(styling::font::SF_PRO_BOLD),
+                    text(&player.artist),
+                    controls,
+                ]
+                .spacing(5)
+                .width(Length::Fill),
+            ]
+            .spacing(15)
+            .align_items(Alignment::Center),
+            progress,
+            switcher,
+        ]
+        .spacing(10),
+    )
+    .style(styling::style::Container::Rounded)
+    .padding(15)
+    .width(Length::Fill)
+    .into()
+}
@@ -0,0 +1,75 @@
+use super::{icon_path, Message, Page};
+use crate::binding;
+use crate::styling;
+
+use iced::{
+    widget::{button, column, container, row, scrollable, slider, svg, text},
+    Alignment, Element, Length,
+};
+
+/// Falls back to the mixer icon for applications we don't ship an icon for.
+fn application_icon(stream: &binding::pulse::PlaybackStream) -> String {
+    stream
+        .icon_name
+        .as_deref()
+        .map(|name| icon_path(&name.replace('-', "_")))
+        .filter(|path| std::path::Path::new(path).exists())
+        .unwrap_or_else(|| icon_path("mixer-alt"))
+}
+
+pub fn view(
+    streams: &[binding::pulse::PlaybackStream],
+) -> Element<'_, Message, styling::theme::Theme> {
+    let icon = |icon_path: String| svg(svg::Handle::from_path(icon_path)).width(25).height(25);
+
+    let header = row![
+        button(icon(icon_path("chevron-left")))
+            .style(styling::style::Button::Icon)
+            .padding(0)
+            .on_press(Message::ShowPage(Page::Main)),
+        text("Mixer").font(styling::font::SF_PRO_BOLD).size(16),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let streams: Element<Message, styling::theme::Theme> = if streams.is_empty() {
+        text("Nothing is playing").into()
+    } else {
+        scrollable(
+            column(streams.iter().map(|stream| {
+                let index = stream.index;
+                let volume_icon = if stream.volume.muted {
+                    "volume-mute"
+                } else {
+                    "volume-up"
+                };
+
+                column![
+                    row![
+                        icon(application_icon(stream)),
+                        text(&stream.application).width(Length::Fill),
+                        button(icon(icon_path(volume_icon)))
+                            .style(styling::style::Button::Icon)
+                            .padding(0)
+                            .on_press(Message::SetStreamMute(index, !stream.volume.muted)),
+                    ]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                    slider(0..=100, stream.volume.volume, move |volume| {
+                        Message::SetStreamVolume(index, volume)
+                    })
+                    .on_release(Message::ReleaseStreamVolume(index)),
+                ]
+                .spacing(5)
+                .into()
+            }))
+            .spacing(15),
+        )
+        .height(Length::Fill)
+        .into()
+    };
+
+    container(column![header, streams].spacing(20))
+        .width(Length::Fill)
+        .into()
+}
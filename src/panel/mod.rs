@@ -1,25 +1,157 @@
+mod bluetooth;
+mod media;
+mod mixer;
+mod notifications;
+pub mod popups;
+mod processes;
+mod storage;
+mod system_monitor;
+mod thermal;
+mod vpn;
+mod wifi;
+
 use crate::binding;
+use crate::config;
 use crate::styling;
 
 use iced::{
-    widget::{button, column, container, row, slider, svg, text},
+    widget::{button, column, container, row, slider, svg, text, tooltip},
     Alignment, Command, Element, Length,
 };
 
+/// `name` is the file name under `assets/icons`, without the extension.
+fn icon_path(name: &str) -> String {
+    format!("{}/assets/icons/{name}.svg", env!("CARGO_MANIFEST_DIR"))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Page {
+    #[default]
+    Main,
+    Mixer,
+    Wifi,
+    Bluetooth,
+    Processes,
+    Notifications,
+}
+
 #[derive(Default)]
 pub struct ControlCenter {
+    page: Page,
+
+    /// Bumped on resume so every binding subscription starts over with fresh state.
+    generation: usize,
+    is_locked: bool,
+
     is_battery_available: bool,
     on_battery: bool,
     percentage: f64,
     time_to_empty: i64,
 
+    is_wifi_available: bool,
+    wireless_enabled: bool,
+    wifi: Option<binding::networkmanager::WifiNetwork>,
+    wifi_networks: Vec<binding::networkmanager::WifiNetwork>,
+    wifi_prompt: Option<binding::networkmanager::WifiNetwork>,
+    wifi_passphrase: String,
+    wifi_error: Option<String>,
+
+    vpns: Vec<binding::networkmanager::VpnConnection>,
+    wired: Option<binding::networkmanager::WiredInfo>,
+    modem: Option<binding::networkmanager::ModemInfo>,
+    vpn_error: Option<String>,
+
+    hotspot: Option<binding::networkmanager::HotspotInfo>,
+    hotspot_config: binding::networkmanager::HotspotConfig,
+    hotspot_clients: Option<usize>,
+    /// The Wi-Fi profile to get back on once the hotspot is turned off.
+    wifi_before_hotspot: Option<zbus::zvariant::OwnedObjectPath>,
+    hotspot_error: Option<String>,
+
+    is_bluetooth_available: bool,
+    bluetooth_powered: bool,
+    bluetooth_devices: Vec<binding::bluez::BluetoothDevice>,
+    bluetooth_discovered: Vec<binding::bluez::BluetoothDevice>,
+    /// Discovery runs while this is set and the Bluetooth page is open.
+    bluetooth_discovering: bool,
+    pairing_request: Option<binding::bluez::PairingRequest>,
+    bluetooth_pending: Option<zbus::zvariant::OwnedObjectPath>,
+    bluetooth_error: Option<String>,
+
+    is_airplane_available: bool,
+    airplane_mode: bool,
+    airplane_hard_blocked: bool,
+    radios: Vec<binding::rfkill::Radio>,
+    /// What to go back to when airplane mode is turned off.
+    radios_before_airplane: Vec<binding::rfkill::Radio>,
+
     master_volume: u32,
+    is_muted: bool,
+
+    mic_volume: Option<u32>,
+    is_mic_muted: bool,
+    recording_applications: Vec<String>,
+
+    sinks: Vec<binding::pulse::AudioDevice>,
+    sources: Vec<binding::pulse::AudioDevice>,
+    is_audio_devices_open: bool,
+    move_streams: bool,
+    playback_streams: Vec<binding::pulse::PlaybackStream>,
+
+    media_players: Vec<binding::mpris::PlayerInfo>,
+    active_media_player: usize,
 
     current_brightness: i32,
     max_brightness: i32,
     min_brightness: i32,
 
+    auto_brightness: bool,
+    ambient_light: Option<f64>,
+    brightness_curve: binding::iio::BrightnessCurve,
+
+    external_monitors: Vec<binding::ddc::MonitorInfo>,
+
+    power_actions: Vec<binding::logind::PowerAction>,
+    is_power_menu_open: bool,
+    pending_power_action: Option<binding::logind::PowerAction>,
+
+    caffeine: Option<binding::logind::InhibitorLock>,
+    /// Set while the inhibitor is being taken, so a second one can't be.
+    caffeine_pending: bool,
+    inhibitors: Vec<binding::logind::Inhibitor>,
+
     active_power_profile: binding::hadess::PowerProfile,
+    platform_profile: Option<binding::platform_profile::PlatformProfileInfo>,
+    platform_profile_error: Option<String>,
+
+    sensors: binding::sensors::SensorReadings,
+    thermal_alerts: binding::sensors::ThermalAlerts,
+    /// Sensors currently over their threshold.
+    raised_thermal_alerts: Vec<binding::sensors::Temperature>,
+
+    monitor_config: binding::system_monitor::MonitorConfig,
+    system_stats: Option<binding::system_monitor::SystemStats>,
+    processes: Vec<binding::system_monitor::Process>,
+    process_sort: binding::system_monitor::ProcessSort,
+    pending_process_signal: Option<(
+        binding::system_monitor::Process,
+        binding::system_monitor::ProcessSignal,
+    )>,
+    process_error: Option<String>,
+
+    filesystems: Vec<binding::mounts::Filesystem>,
+    removable_drives: Vec<binding::udisks::RemovableDrive>,
+    /// The drive or volume being mounted, unmounted or ejected.
+    storage_pending: Option<zbus::zvariant::OwnedObjectPath>,
+    storage_error: Option<String>,
+
+    notification_server: Option<binding::notifications::NotificationServer>,
+    /// Newest first.
+    notifications: Vec<binding::notifications::Notification>,
+    popups: Vec<notifications::Popup>,
+    /// Where the popups are shown, on a surface of their own.
+    popup_link: Option<popups::PanelLink>,
+    do_not_disturb: binding::notifications::DoNotDisturb,
 }
 
 #[derive(Debug, Clone)]
@@ -27,27 +159,167 @@ pub enum Message {
     UPowerDevice(binding::upower::BatteryInfo),
     HadessDevice(binding::hadess::PowerProfileInfo),
     ScreenDevice(binding::logind::DisplayInfo),
+    Session(binding::logind::SessionEvent),
+    Network(binding::networkmanager::NetworkInfo),
+    Bluetooth(binding::bluez::BluetoothInfo),
+    BluetoothAgent(binding::bluez::AgentEvent),
+    Rfkill(binding::rfkill::RfkillInfo),
+    AudioDevice(binding::pulse::AudioInfo),
+    MediaPlayers(binding::mpris::MprisInfo),
+    AmbientLightSensor(binding::iio::LightInfo),
+    PlatformProfile(binding::platform_profile::PlatformProfileInfo),
+    SensorTick,
+    Sensors(binding::sensors::SensorReadings),
+    SystemStats(binding::system_monitor::SystemStats),
+    Processes(Vec<binding::system_monitor::Process>),
+    SortProcesses(binding::system_monitor::ProcessSort),
+    RequestProcessSignal(
+        binding::system_monitor::Process,
+        binding::system_monitor::ProcessSignal,
+    ),
+    ConfirmProcessSignal,
+    CancelProcessSignal,
+    ProcessSignalSent(Result<(), String>),
+    Storage(binding::udisks::StorageInfo),
+    FilesystemTick,
+    Filesystems(Vec<binding::mounts::Filesystem>),
+    MountVolume(binding::udisks::Volume),
+    UnmountVolume(binding::udisks::Volume),
+    EjectDrive(binding::udisks::RemovableDrive),
+    StorageActionDone(Result<(), String>),
+    Notifications(binding::notifications::NotificationEvent),
+    NotificationTick,
+    HidePopup(u32),
+    DismissNotification(u32),
+    ClearNotifications,
+    InvokeNotificationAction(u32, String),
+    NotificationSignalSent(Result<(), String>),
+    ToggleDoNotDisturb,
 
+    ShowPage(Page),
+    ToggleWifi,
+    GetWifiEnabled(bool),
+    WifiScanRequested,
+    SelectWifiNetwork(binding::networkmanager::WifiNetwork),
+    SetWifiPassphrase(String),
+    ConnectWifi,
+    CancelWifiPassphrase,
+    WifiConnected(Result<(), String>),
+    SetVpnActive(binding::networkmanager::VpnConnection, bool),
+    VpnToggled(Result<(), String>),
+    ToggleHotspot,
+    SetHotspotSsid(String),
+    SetHotspotPassword(String),
+    HotspotToggled(Result<(), String>),
+    HotspotTick,
+    HotspotClients(Option<usize>),
+    ToggleBluetooth,
+    GetBluetoothPowered(bool),
+    SetBluetoothConnected(zbus::zvariant::OwnedObjectPath, bool),
+    BluetoothConnected(Result<(), String>),
+    ToggleBluetoothDiscovery,
+    BluetoothDiscovery(Result<(), String>),
+    PairBluetoothDevice(zbus::zvariant::OwnedObjectPath),
+    AnswerPairing(bool),
+    ToggleAirplaneMode,
+    GetAirplaneMode(bool),
     SetMasterVolume(u32),
+    ReleaseMasterVolume,
+    GetMasterVolume(u32),
+    ToggleMute,
+    GetMute(bool),
+    SetMicVolume(u32),
+    ReleaseMicVolume,
+    GetMicVolume(u32),
+    ToggleMicMute,
+    GetMicMute(bool),
+    ToggleAudioDevices,
+    ToggleMoveStreams,
+    SetDefaultAudioDevice(binding::pulse::Direction, String),
+    GetDefaultAudioDevice(binding::pulse::Direction, String),
+    SetStreamVolume(u32, u32),
+    ReleaseStreamVolume(u32),
+    GetStreamVolume(u32, u32),
+    SetStreamMute(u32, bool),
+    GetStreamMute(u32, bool),
+    NextMediaPlayer,
+    ControlMediaPlayer(binding::mpris::PlayerAction),
+    MediaPlayerControlled(binding::mpris::PlayerAction),
+    SeekMediaPlayer(u32),
+    ReleaseMediaSeek,
+    MediaTick,
     SetBrightness(i32),
     GetBrightness(i32),
+    ReleaseBrightness,
+    ToggleAutoBrightness,
+    ExternalMonitors(Vec<binding::ddc::MonitorInfo>),
+    SetExternalBrightness(usize, u16),
+    ReleaseExternalBrightness(usize),
+    GetExternalBrightness(usize, Option<u16>),
+    PowerActions(Vec<binding::logind::PowerAction>),
+    TogglePowerMenu,
+    RequestPowerAction(binding::logind::PowerAction),
+    ConfirmPowerAction,
+    CancelPowerAction,
+    PowerActionPerformed(binding::logind::PowerAction),
+    ToggleCaffeine,
+    Caffeine(Option<binding::logind::InhibitorLock>),
+    Inhibitors(Vec<binding::logind::Inhibitor>),
+    ConfigSaved,
+    NotificationSent,
     ToggleProfiles,
+    CycleFanProfile,
+    FanProfileCycled(Result<binding::platform_profile::PlatformProfileInfo, String>),
+}
+
+impl ControlCenter {
+    /// Sends the popups still up to their surface, oldest first.
+    fn show_popups(&self) {
+        let Some(link) = &self.popup_link else {
+            return;
+        };
+
+        link.show(
+            self.popups
+                .iter()
+                .filter_map(|popup| {
+                    self.notifications
+                        .iter()
+                        .find(|notification| notification.id == popup.id)
+                })
+                .cloned()
+                .collect(),
+        );
+    }
 }
 
 impl iced_layershell::Application for ControlCenter {
     type Executor = iced::executor::Default;
     type Message = Message;
     type Theme = styling::theme::Theme;
-    type Flags = ();
+    type Flags = popups::PanelLink;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
+    fn new(popup_link: popups::PanelLink) -> (Self, Command<Message>) {
         (
             Self {
-                master_volume: 100,
                 on_battery: true,
+                brightness_curve: config::load("brightness-curve"),
+                hotspot_config: config::load("hotspot"),
+                thermal_alerts: config::load("thermal-alerts"),
+                monitor_config: config::load::<binding::system_monitor::MonitorConfig>(
+                    "system-monitor",
+                )
+                .clamped(),
+                do_not_disturb: config::load("do-not-disturb"),
+                popup_link: Some(popup_link),
                 ..Default::default()
             },
-            Command::none(),
+            Command::batch([
+                Command::perform(binding::ddc::scan_monitors(), Message::ExternalMonitors),
+                Command::perform(binding::logind::inhibitors(), Message::Inhibitors),
+                Command::perform(binding::logind::power_actions(), Message::PowerActions),
+                Command::perform(binding::mounts::filesystems(), Message::Filesystems),
+            ]),
         )
     }
 
@@ -94,12 +366,826 @@ impl iced_layershell::Application for ControlCenter {
             Message::GetBrightness(value) => {
                 self.current_brightness = value;
             }
+            Message::AmbientLightSensor(event) => match event {
+                binding::iio::LightInfo::NotAvailable => {
+                    self.auto_brightness = false;
+                    self.ambient_light = None;
+                }
+                binding::iio::LightInfo::Level(lux) => {
+                    self.ambient_light = Some(lux);
+
+                    let range = (self.max_brightness - self.min_brightness) as f64;
+                    let target = self.min_brightness
+                        + (self.brightness_curve.brightness(lux) * range).round() as i32;
+
+                    // Small sensor jitter shouldn't make the screen flicker.
+                    if (target - self.current_brightness).abs() as f64 > range * 0.02 {
+                        let command = binding::logind::set_brightness(target);
+                        return Command::perform(command, Message::GetBrightness);
+                    }
+                }
+            },
+            Message::ReleaseBrightness => {
+                let range = (self.max_brightness - self.min_brightness) as f64;
+
+                let learning = self.auto_brightness && range > 0.0;
+
+                if let Some(lux) = self.ambient_light.filter(|_| learning) {
+                    let fraction = (self.current_brightness - self.min_brightness) as f64 / range;
+                    self.brightness_curve.learn(lux, fraction);
+
+                    let command = config::save("brightness-curve", self.brightness_curve.clone());
+                    return Command::perform(command, |_| Message::ConfigSaved);
+                }
+            }
+            Message::Session(event) => match event {
+                binding::logind::SessionEvent::Resumed => {
+                    self.generation = self.generation.wrapping_add(1);
+
+                    // Whether hibernating is possible depends on swap, among
+                    // others, which may have changed in the meantime.
+                    return Command::batch([
+                        Command::perform(binding::ddc::scan_monitors(), Message::ExternalMonitors),
+                        Command::perform(binding::logind::inhibitors(), Message::Inhibitors),
+                        Command::perform(binding::logind::power_actions(), Message::PowerActions),
+                    ]);
+                }
+                binding::logind::SessionEvent::Locked => {
+                    self.is_locked = true;
+                    self.is_power_menu_open = false;
+                    self.pending_power_action = None;
+                }
+                binding::logind::SessionEvent::Unlocked => {
+                    self.is_locked = false;
+                }
+                binding::logind::SessionEvent::Sleeping
+                | binding::logind::SessionEvent::ShuttingDown => {}
+            },
+            Message::ExternalMonitors(monitors) => {
+                self.external_monitors = monitors;
+            }
+            Message::SetExternalBrightness(index, value) => {
+                if let Some(monitor) = self.external_monitors.get_mut(index) {
+                    monitor.brightness = value;
+                }
+            }
+            Message::ReleaseExternalBrightness(index) => {
+                // DDC/CI is slow, so the value is only sent once the slider is let go.
+                if let Some(monitor) = self.external_monitors.get(index) {
+                    let command =
+                        binding::ddc::set_brightness(monitor.path.clone(), monitor.brightness);
+                    return Command::perform(command, move |value| {
+                        Message::GetExternalBrightness(index, value)
+                    });
+                }
+            }
+            Message::GetExternalBrightness(index, value) => match value {
+                Some(value) => {
+                    if let Some(monitor) = self.external_monitors.get_mut(index) {
+                        monitor.brightness = value;
+                    }
+                }
+                // Unplugged, most likely.
+                None => {
+                    return Command::perform(
+                        binding::ddc::scan_monitors(),
+                        Message::ExternalMonitors,
+                    );
+                }
+            },
+            Message::PowerActions(actions) => {
+                self.power_actions = actions;
+            }
+            Message::TogglePowerMenu => {
+                self.is_power_menu_open = !self.is_power_menu_open;
+                self.pending_power_action = None;
+            }
+            Message::RequestPowerAction(action) => {
+                if action.is_destructive() {
+                    self.pending_power_action = Some(action);
+                } else {
+                    let command = binding::logind::perform(action);
+                    return Command::perform(command, Message::PowerActionPerformed);
+                }
+            }
+            Message::ConfirmPowerAction => {
+                if let Some(action) = self.pending_power_action.take() {
+                    let command = binding::logind::perform(action);
+                    return Command::perform(command, Message::PowerActionPerformed);
+                }
+            }
+            Message::CancelPowerAction => {
+                self.pending_power_action = None;
+            }
+            Message::PowerActionPerformed(_) => {
+                self.is_power_menu_open = false;
+            }
+            Message::ToggleCaffeine => {
+                // Dropping the lock closes the descriptor, which releases the inhibitor.
+                if self.caffeine.take().is_none() && !self.caffeine_pending {
+                    self.caffeine_pending = true;
+                    return Command::perform(binding::logind::inhibit_idle(), Message::Caffeine);
+                }
+            }
+            Message::Caffeine(lock) => {
+                self.caffeine_pending = false;
+                self.caffeine = lock;
+                return Command::perform(binding::logind::inhibitors(), Message::Inhibitors);
+            }
+            Message::Inhibitors(inhibitors) => {
+                self.inhibitors = inhibitors;
+            }
+            Message::ConfigSaved | Message::NotificationSent => {}
+            Message::ToggleAutoBrightness => {
+                self.auto_brightness = !self.auto_brightness;
+                self.ambient_light = None;
+            }
             Message::ToggleProfiles => {
                 println!("Toggle Profiles");
             }
+            Message::PlatformProfile(info) => {
+                self.platform_profile = Some(info);
+            }
+            Message::SensorTick => {
+                let command = binding::sensors::read(binding::sensors::Sensors::system());
+                return Command::perform(command, Message::Sensors);
+            }
+            Message::Sensors(readings) => {
+                let alerts = self
+                    .thermal_alerts
+                    .check(&readings, &self.raised_thermal_alerts);
+                self.sensors = readings;
+
+                // Only newly raised alerts are worth a notification.
+                let raised: Vec<binding::sensors::Temperature> = alerts
+                    .iter()
+                    .filter(|alert| {
+                        !self
+                            .raised_thermal_alerts
+                            .iter()
+                            .any(|previous| previous.kind == alert.kind)
+                    })
+                    .cloned()
+                    .collect();
+                self.raised_thermal_alerts = alerts;
+
+                return Command::batch(raised.into_iter().map(|alert| {
+                    let command = binding::notifications::send(
+                        format!("{} is running hot", alert.kind),
+                        format!("{:.0}°C ({} {})", alert.celsius, alert.chip, alert.label),
+                        binding::notifications::Urgency::Critical,
+                    );
+                    Command::perform(command, |_| Message::NotificationSent)
+                }));
+            }
+            Message::SystemStats(stats) => {
+                self.system_stats = Some(stats);
+            }
+            Message::Processes(mut processes) => {
+                binding::system_monitor::sort_processes(&mut processes, self.process_sort);
+                self.processes = processes;
+            }
+            Message::SortProcesses(sort) => {
+                self.process_sort = sort;
+                binding::system_monitor::sort_processes(&mut self.processes, sort);
+            }
+            Message::RequestProcessSignal(process, signal) => {
+                self.process_error = None;
+                self.pending_process_signal = Some((process, signal));
+            }
+            Message::ConfirmProcessSignal => {
+                if let Some((process, signal)) = self.pending_process_signal.take() {
+                    let command = binding::system_monitor::send_signal(process, signal);
+                    return Command::perform(command, Message::ProcessSignalSent);
+                }
+            }
+            Message::CancelProcessSignal => {
+                self.pending_process_signal = None;
+            }
+            Message::ProcessSignalSent(result) => {
+                self.process_error = result.err();
+            }
+            Message::Storage(event) => {
+                self.removable_drives = match event {
+                    binding::udisks::StorageInfo::NotAvailable => Vec::new(),
+                    binding::udisks::StorageInfo::Available { drives } => drives,
+                };
+
+                // Something may have been mounted or unmounted.
+                return Command::perform(binding::mounts::filesystems(), Message::Filesystems);
+            }
+            Message::FilesystemTick => {
+                return Command::perform(binding::mounts::filesystems(), Message::Filesystems);
+            }
+            Message::Filesystems(filesystems) => {
+                self.filesystems = filesystems;
+            }
+            Message::MountVolume(volume) => {
+                self.storage_error = None;
+                self.storage_pending = Some(volume.path.clone());
+
+                let command = binding::udisks::mount(volume);
+                return Command::perform(command, Message::StorageActionDone);
+            }
+            Message::UnmountVolume(volume) => {
+                self.storage_error = None;
+                self.storage_pending = Some(volume.path.clone());
+
+                let command = binding::udisks::unmount(volume);
+                return Command::perform(command, Message::StorageActionDone);
+            }
+            Message::EjectDrive(drive) => {
+                self.storage_error = None;
+                self.storage_pending = Some(drive.path.clone());
+
+                let command = binding::udisks::eject(drive);
+                return Command::perform(command, Message::StorageActionDone);
+            }
+            Message::StorageActionDone(result) => {
+                self.storage_pending = None;
+                self.storage_error = result.err();
+            }
+            Message::Notifications(event) => match event {
+                binding::notifications::NotificationEvent::Started(server) => {
+                    self.notification_server = Some(server);
+                }
+                binding::notifications::NotificationEvent::Received(notification) => {
+                    let popup = notifications::Popup {
+                        id: notification.id,
+                        expires: notification
+                            .timeout
+                            .map(|timeout| std::time::Instant::now() + timeout),
+                    };
+
+                    let time = binding::notifications::TimeOfDay::now();
+                    let allowed = self.do_not_disturb.allows(&notification, time);
+
+                    // A replacement keeps its id, so the old one goes.
+                    self.notifications
+                        .retain(|known| known.id != notification.id);
+                    self.notifications.insert(0, notification);
+                    let dropped = self
+                        .notifications
+                        .split_off(self.notifications.len().min(notifications::HISTORY_LIMIT));
+
+                    self.popups.retain(|known| known.id != popup.id);
+                    if allowed {
+                        self.popups.push(popup);
+                    }
+                    self.show_popups();
+
+                    // Senders keep track of their notifications until told
+                    // they're gone, which they are once out of the history.
+                    if let Some(server) = &self.notification_server {
+                        return Command::batch(dropped.into_iter().map(|notification| {
+                            let command = binding::notifications::close(
+                                server.clone(),
+                                notification.id,
+                                binding::notifications::CloseReason::Dismissed,
+                            );
+                            Command::perform(command, Message::NotificationSignalSent)
+                        }));
+                    }
+                }
+                binding::notifications::NotificationEvent::Closed(id) => {
+                    self.notifications
+                        .retain(|notification| notification.id != id);
+                    self.popups.retain(|popup| popup.id != id);
+                    self.show_popups();
+                }
+            },
+            Message::NotificationTick => {
+                // Expired popups are still in the history, so they aren't closed.
+                let now = std::time::Instant::now();
+                let count = self.popups.len();
+                self.popups
+                    .retain(|popup| popup.expires.is_none_or(|expires| expires > now));
+
+                if self.popups.len() != count {
+                    self.show_popups();
+                }
+            }
+            Message::HidePopup(id) => {
+                self.popups.retain(|popup| popup.id != id);
+                self.show_popups();
+            }
+            Message::DismissNotification(id) => {
+                self.notifications
+                    .retain(|notification| notification.id != id);
+                self.popups.retain(|popup| popup.id != id);
+                self.show_popups();
+
+                if let Some(server) = self.notification_server.clone() {
+                    let command = binding::notifications::close(
+                        server,
+                        id,
+                        binding::notifications::CloseReason::Dismissed,
+                    );
+                    return Command::perform(command, Message::NotificationSignalSent);
+                }
+            }
+            Message::ClearNotifications => {
+                self.popups.clear();
+                self.show_popups();
+                let cleared = std::mem::take(&mut self.notifications);
+
+                if let Some(server) = &self.notification_server {
+                    return Command::batch(cleared.into_iter().map(|notification| {
+                        let command = binding::notifications::close(
+                            server.clone(),
+                            notification.id,
+                            binding::notifications::CloseReason::Dismissed,
+                        );
+                        Command::perform(command, Message::NotificationSignalSent)
+                    }));
+                }
+            }
+            Message::InvokeNotificationAction(id, action_key) => {
+                self.notifications
+                    .retain(|notification| notification.id != id);
+                self.popups.retain(|popup| popup.id != id);
+                self.show_popups();
+
+                if let Some(server) = self.notification_server.clone() {
+                    let command = binding::notifications::invoke_action(server, id, action_key);
+                    return Command::perform(command, Message::NotificationSignalSent);
+                }
+            }
+            Message::NotificationSignalSent(_) => {}
+            Message::ToggleDoNotDisturb => {
+                self.do_not_disturb.enabled = !self.do_not_disturb.enabled;
+
+                // Popups already up go too, unless they'd have got through.
+                let time = binding::notifications::TimeOfDay::now();
+                let (do_not_disturb, notifications) = (&self.do_not_disturb, &self.notifications);
+                self.popups.retain(|popup| {
+                    notifications
+                        .iter()
+                        .find(|notification| notification.id == popup.id)
+                        .is_some_and(|notification| do_not_disturb.allows(notification, time))
+                });
+                self.show_popups();
+
+                let command = config::save("do-not-disturb", self.do_not_disturb.clone());
+                return Command::perform(command, |_| Message::ConfigSaved);
+            }
+            Message::CycleFanProfile => {
+                let next = self.platform_profile.as_ref().and_then(|info| info.next());
+
+                if let Some(next) = next {
+                    let command = binding::platform_profile::set_profile(
+                        binding::platform_profile::PlatformProfile::system(),
+                        next.to_owned(),
+                    );
+                    return Command::perform(command, Message::FanProfileCycled);
+                }
+            }
+            Message::FanProfileCycled(result) => match result {
+                Ok(info) => {
+                    self.platform_profile = Some(info);
+                    self.platform_profile_error = None;
+                }
+                Err(err) => self.platform_profile_error = Some(err),
+            },
+            Message::Network(event) => match event {
+                binding::networkmanager::NetworkInfo::NotAvailable => {
+                    self.is_wifi_available = false;
+                    self.wireless_enabled = false;
+                    self.wifi = None;
+                    self.wifi_networks.clear();
+                    self.vpns.clear();
+                    self.wired = None;
+                    self.modem = None;
+                    self.hotspot = None;
+                    self.hotspot_clients = None;
+                }
+                binding::networkmanager::NetworkInfo::Available {
+                    is_wifi_available,
+                    wireless_enabled,
+                    wifi,
+                    networks,
+                    vpns,
+                    wired,
+                    modem,
+                    hotspot,
+                } => {
+                    self.is_wifi_available = is_wifi_available;
+                    self.wireless_enabled = wireless_enabled;
+                    self.wifi = wifi;
+                    self.wifi_networks = networks;
+                    self.vpns = vpns;
+                    self.wired = wired;
+                    self.modem = modem;
+
+                    if hotspot.is_none() {
+                        self.hotspot_clients = None;
+                    }
+                    self.hotspot = hotspot;
+                }
+            },
+            Message::ToggleWifi => {
+                let command = binding::networkmanager::set_wireless_enabled(!self.wireless_enabled);
+                return Command::perform(command, Message::GetWifiEnabled);
+            }
+            Message::GetWifiEnabled(enabled) => {
+                self.wireless_enabled = enabled;
+            }
+            Message::WifiScanRequested => {}
+            Message::SelectWifiNetwork(network) => {
+                self.wifi_error = None;
+                self.wifi_passphrase.clear();
+
+                if network.needs_passphrase() {
+                    self.wifi_prompt = Some(network);
+                } else {
+                    self.wifi_prompt = None;
+                    let command = binding::networkmanager::connect(network, String::new());
+                    return Command::perform(command, Message::WifiConnected);
+                }
+            }
+            Message::SetWifiPassphrase(passphrase) => {
+                self.wifi_passphrase = passphrase;
+            }
+            Message::ConnectWifi => {
+                if let Some(network) = self.wifi_prompt.take() {
+                    let passphrase = std::mem::take(&mut self.wifi_passphrase);
+                    let command = binding::networkmanager::connect(network, passphrase);
+                    return Command::perform(command, Message::WifiConnected);
+                }
+            }
+            Message::CancelWifiPassphrase => {
+                self.wifi_prompt = None;
+                self.wifi_passphrase.clear();
+            }
+            Message::WifiConnected(result) => {
+                self.wifi_error = result.err();
+            }
+            Message::SetVpnActive(vpn, active) => {
+                self.vpn_error = None;
+
+                let command = binding::networkmanager::set_vpn_active(vpn, active);
+                return Command::perform(command, Message::VpnToggled);
+            }
+            Message::VpnToggled(result) => {
+                self.vpn_error = result.err();
+            }
+            Message::ToggleHotspot => {
+                self.hotspot_error = None;
+
+                if let Some(hotspot) = self.hotspot.clone() {
+                    let previous = self.wifi_before_hotspot.take();
+                    let command = binding::networkmanager::stop_hotspot(hotspot, previous);
+                    return Command::perform(command, Message::HotspotToggled);
+                }
+
+                if self.hotspot_config.is_valid() {
+                    self.wifi_before_hotspot =
+                        self.wifi.as_ref().and_then(|wifi| wifi.connection.clone());
+
+                    let config = self.hotspot_config.clone();
+                    return Command::batch([
+                        Command::perform(
+                            binding::networkmanager::start_hotspot(config.clone()),
+                            Message::HotspotToggled,
+                        ),
+                        Command::perform(config::save("hotspot", config), |_| Message::ConfigSaved),
+                    ]);
+                }
+            }
+            Message::SetHotspotSsid(ssid) => {
+                self.hotspot_config.ssid = ssid;
+            }
+            Message::SetHotspotPassword(password) => {
+                self.hotspot_config.password = password;
+            }
+            Message::HotspotToggled(result) => {
+                self.hotspot_error = result.err();
+            }
+            Message::HotspotTick => {
+                // Clients joining or leaving aren't announced, so they're counted now and then.
+                if let Some(hotspot) = &self.hotspot {
+                    let command =
+                        binding::networkmanager::hotspot_clients(hotspot.interface.clone());
+                    return Command::perform(command, Message::HotspotClients);
+                }
+            }
+            Message::HotspotClients(clients) => {
+                self.hotspot_clients = clients;
+            }
+            Message::Bluetooth(event) => match event {
+                binding::bluez::BluetoothInfo::NotAvailable => {
+                    self.is_bluetooth_available = false;
+                    self.bluetooth_powered = false;
+                    self.bluetooth_devices.clear();
+                    self.bluetooth_discovered.clear();
+                }
+                binding::bluez::BluetoothInfo::Available {
+                    powered,
+                    discovering,
+                    devices,
+                    discovered,
+                } => {
+                    self.is_bluetooth_available = true;
+                    self.bluetooth_powered = powered;
+                    self.bluetooth_devices = devices;
+                    // Devices seen by someone else's scan are kept out of our way.
+                    self.bluetooth_discovered = if discovering && self.bluetooth_discovering {
+                        discovered
+                    } else {
+                        Vec::new()
+                    };
+                }
+            },
+            Message::ToggleBluetooth => {
+                let command = binding::bluez::set_powered(!self.bluetooth_powered);
+                return Command::perform(command, Message::GetBluetoothPowered);
+            }
+            Message::GetBluetoothPowered(powered) => {
+                self.bluetooth_powered = powered;
+            }
+            Message::SetBluetoothConnected(path, connected) => {
+                self.bluetooth_error = None;
+                self.bluetooth_pending = Some(path.clone());
+
+                let command = binding::bluez::set_connected(path, connected);
+                return Command::perform(command, Message::BluetoothConnected);
+            }
+            Message::BluetoothConnected(result) => {
+                self.bluetooth_pending = None;
+                self.bluetooth_error = result.err();
+            }
+            Message::ToggleBluetoothDiscovery => {
+                self.bluetooth_discovering = !self.bluetooth_discovering;
+
+                if !self.bluetooth_discovering {
+                    self.bluetooth_discovered.clear();
+                }
+            }
+            Message::BluetoothDiscovery(result) => {
+                if let Err(err) = result {
+                    self.bluetooth_discovering = false;
+                    self.bluetooth_error = Some(err);
+                }
+            }
+            Message::PairBluetoothDevice(path) => {
+                self.bluetooth_error = None;
+                self.bluetooth_pending = Some(path.clone());
+
+                let command = binding::bluez::pair(path);
+                return Command::perform(command, Message::BluetoothConnected);
+            }
+            Message::BluetoothAgent(event) => match event {
+                binding::bluez::AgentEvent::Request(request) => {
+                    // A newer request supersedes whatever was still on screen.
+                    if let Some(previous) = self.pairing_request.replace(request) {
+                        previous.reply.send(false);
+                    }
+                }
+                binding::bluez::AgentEvent::Cancelled => {
+                    self.pairing_request = None;
+                }
+            },
+            Message::AnswerPairing(accepted) => {
+                if let Some(request) = self.pairing_request.take() {
+                    request.reply.send(accepted);
+                }
+            }
+            Message::Rfkill(event) => match event {
+                binding::rfkill::RfkillInfo::NotAvailable => {
+                    self.is_airplane_available = false;
+                    self.airplane_mode = false;
+                    self.airplane_hard_blocked = false;
+                    self.radios.clear();
+                }
+                binding::rfkill::RfkillInfo::Available {
+                    airplane_mode,
+                    hard_blocked,
+                    radios,
+                } => {
+                    self.is_airplane_available = true;
+                    self.airplane_mode = airplane_mode;
+                    self.airplane_hard_blocked = hard_blocked;
+                    self.radios = radios;
+                }
+            },
+            Message::ToggleAirplaneMode => {
+                let command = if self.airplane_mode {
+                    let previous = std::mem::take(&mut self.radios_before_airplane);
+                    binding::rfkill::set_airplane_mode(false, previous)
+                } else {
+                    self.radios_before_airplane = self.radios.clone();
+                    binding::rfkill::set_airplane_mode(true, Vec::new())
+                };
+                return Command::perform(command, Message::GetAirplaneMode);
+            }
+            Message::GetAirplaneMode(enabled) => {
+                self.airplane_mode = enabled;
+            }
+            Message::AudioDevice(event) => match event {
+                binding::pulse::AudioInfo::NotAvailable => {
+                    self.master_volume = 0;
+                    self.is_muted = true;
+                    self.mic_volume = None;
+                    self.recording_applications.clear();
+                    self.sinks.clear();
+                    self.sources.clear();
+                    self.playback_streams.clear();
+                }
+                binding::pulse::AudioInfo::Available {
+                    output,
+                    input,
+                    recording,
+                    sinks,
+                    sources,
+                    playback,
+                } => {
+                    let output = output.unwrap_or(binding::pulse::Volume {
+                        volume: 0,
+                        muted: true,
+                    });
+                    self.master_volume = output.volume;
+                    self.is_muted = output.muted;
+                    self.mic_volume = input.map(|input| input.volume);
+                    self.is_mic_muted = input.is_some_and(|input| input.muted);
+                    self.recording_applications = recording;
+                    self.sinks = sinks;
+                    self.sources = sources;
+                    self.playback_streams = playback;
+                }
+            },
+            Message::MediaPlayers(event) => match event {
+                binding::mpris::MprisInfo::Players(players) => {
+                    // Stick to the same player when others come and go.
+                    let active = self
+                        .media_players
+                        .get(self.active_media_player)
+                        .map(|player| player.bus_name.clone());
+                    self.active_media_player = active
+                        .and_then(|name| players.iter().position(|p| p.bus_name == name))
+                        .or_else(|| {
+                            players
+                                .iter()
+                                .position(|p| p.status == binding::mpris::PlaybackStatus::Playing)
+                        })
+                        .unwrap_or_default();
+                    self.media_players = players;
+                }
+            },
+            Message::NextMediaPlayer => {
+                if !self.media_players.is_empty() {
+                    self.active_media_player =
+                        (self.active_media_player + 1) % self.media_players.len();
+                }
+            }
+            Message::ControlMediaPlayer(action) => {
+                if let Some(player) = self.media_players.get(self.active_media_player) {
+                    let command = binding::mpris::control(
+                        player.bus_name.clone(),
+                        player.track_id.clone(),
+                        action,
+                    );
+                    return Command::perform(command, Message::MediaPlayerControlled);
+                }
+            }
+            Message::MediaPlayerControlled(action) => {
+                if let (binding::mpris::PlayerAction::SetPosition(position), Some(player)) =
+                    (action, self.media_players.get_mut(self.active_media_player))
+                {
+                    player.position = position;
+                }
+            }
+            Message::SeekMediaPlayer(seconds) => {
+                if let Some(player) = self.media_players.get_mut(self.active_media_player) {
+                    player.position = seconds as i64 * 1_000_000;
+                }
+            }
+            Message::ReleaseMediaSeek => {
+                if let Some(player) = self.media_players.get(self.active_media_player) {
+                    let action = binding::mpris::PlayerAction::SetPosition(player.position);
+                    return self.update(Message::ControlMediaPlayer(action));
+                }
+            }
+            Message::MediaTick => {
+                // Players don't announce position changes, so it's extrapolated locally.
+                for player in &mut self.media_players {
+                    if player.status == binding::mpris::PlaybackStatus::Playing {
+                        player.position = (player.position + 1_000_000).min(player.length);
+                    }
+                }
+            }
+            Message::ShowPage(page) => {
+                self.page = page;
+
+                // The agent is gone along with the page.
+                if page != Page::Bluetooth {
+                    if let Some(request) = self.pairing_request.take() {
+                        request.reply.send(false);
+                    }
+                }
+
+                // Measured afresh every time the page is opened.
+                if page != Page::Processes {
+                    self.processes.clear();
+                    self.pending_process_signal = None;
+                    self.process_error = None;
+                }
+
+                if page == Page::Wifi {
+                    let command = binding::networkmanager::request_scan();
+                    return Command::perform(command, |_| Message::WifiScanRequested);
+                }
+            }
             Message::SetMasterVolume(value) => {
                 self.master_volume = value;
-                println!("Setting master volume: {value}");
+            }
+            Message::ReleaseMasterVolume => {
+                // Replies to every tick could come back out of order, so the
+                // value is only sent once the slider is let go.
+                let command = binding::pulse::set_sink_volume(self.master_volume);
+                return Command::perform(command, Message::GetMasterVolume);
+            }
+            Message::GetMasterVolume(value) => {
+                self.master_volume = value;
+            }
+            Message::ToggleMute => {
+                let command = binding::pulse::set_sink_mute(!self.is_muted);
+                return Command::perform(command, Message::GetMute);
+            }
+            Message::GetMute(muted) => {
+                self.is_muted = muted;
+            }
+            Message::SetMicVolume(value) => {
+                self.mic_volume = Some(value);
+            }
+            Message::ReleaseMicVolume => {
+                // Like the master volume, only sent once the slider is let go.
+                if let Some(value) = self.mic_volume {
+                    let command = binding::pulse::set_source_volume(value);
+                    return Command::perform(command, Message::GetMicVolume);
+                }
+            }
+            Message::GetMicVolume(value) => {
+                self.mic_volume = Some(value);
+            }
+            Message::ToggleMicMute => {
+                let command = binding::pulse::set_source_mute(!self.is_mic_muted);
+                return Command::perform(command, Message::GetMicMute);
+            }
+            Message::GetMicMute(muted) => {
+                self.is_mic_muted = muted;
+            }
+            Message::ToggleAudioDevices => {
+                self.is_audio_devices_open = !self.is_audio_devices_open;
+            }
+            Message::ToggleMoveStreams => {
+                self.move_streams = !self.move_streams;
+            }
+            Message::SetDefaultAudioDevice(direction, name) => {
+                let command =
+                    binding::pulse::set_default_device(direction, name, self.move_streams);
+                return Command::perform(command, move |name| {
+                    Message::GetDefaultAudioDevice(direction, name)
+                });
+            }
+            Message::SetStreamVolume(index, volume) => {
+                if let Some(stream) = self.playback_streams.iter_mut().find(|s| s.index == index) {
+                    stream.volume.volume = volume;
+                }
+            }
+            Message::ReleaseStreamVolume(index) => {
+                // Like the master volume, only sent once the slider is let go.
+                if let Some(stream) = self.playback_streams.iter().find(|s| s.index == index) {
+                    let command = binding::pulse::set_stream_volume(index, stream.volume.volume);
+                    return Command::perform(command, |(index, volume)| {
+                        Message::GetStreamVolume(index, volume)
+                    });
+                }
+            }
+            Message::GetStreamVolume(index, volume) => {
+                if let Some(stream) = self.playback_streams.iter_mut().find(|s| s.index == index) {
+                    stream.volume.volume = volume;
+                }
+            }
+            Message::SetStreamMute(index, muted) => {
+                let command = binding::pulse::set_stream_mute(index, muted);
+                return Command::perform(command, |(index, muted)| {
+                    Message::GetStreamMute(index, muted)
+                });
+            }
+            Message::GetStreamMute(index, muted) => {
+                if let Some(stream) = self.playback_streams.iter_mut().find(|s| s.index == index) {
+                    stream.volume.muted = muted;
+                }
+            }
+            Message::GetDefaultAudioDevice(direction, name) => {
+                let devices = match direction {
+                    binding::pulse::Direction::Output => &mut self.sinks,
+                    binding::pulse::Direction::Input => &mut self.sources,
+                };
+
+                for device in devices {
+                    device.is_default = device.name == name;
+                }
             }
         }
 
@@ -107,21 +1193,59 @@ impl iced_layershell::Application for ControlCenter {
     }
 
     fn view(&self) -> Element<Message, Self::Theme> {
-        let battery_icon = format!(
-            "{}/assets/icons/battery{}-{}.svg",
-            env!("CARGO_MANIFEST_DIR"),
+        // The background is transparent, so an empty surface hides the panel.
+        if self.is_locked {
+            return container(column![]).into();
+        }
+
+        let battery_icon = icon_path(&format!(
+            "battery{}-{}",
             if self.on_battery { "" } else { "-charging" },
             ((self.percentage as i32 + 5) / 10) * 10
-        );
-        let wifi_icon = format!("{}/assets/icons/wifi-full.svg", env!("CARGO_MANIFEST_DIR"));
-        let blue_icon = format!("{}/assets/icons/bluetooth.svg", env!("CARGO_MANIFEST_DIR"));
-        let plane_icon = format!("{}/assets/icons/airplane.svg", env!("CARGO_MANIFEST_DIR"));
-        let power_icon = format!("{}/assets/icons/power-mode.svg", env!("CARGO_MANIFEST_DIR"));
-        let fan_icon = format!("{}/assets/icons/fan.svg", env!("CARGO_MANIFEST_DIR"));
-        let volume_icon = format!("{}/assets/icons/volume-up.svg", env!("CARGO_MANIFEST_DIR"));
-        let bright_icon = format!("{}/assets/icons/brightness.svg", env!("CARGO_MANIFEST_DIR"));
-
-        // TODO: Network Manager, add power profile dropdown menu, change background to transparent, add degraded performance to power profile
+        ));
+        let wifi_icon = icon_path(match &self.wifi {
+            Some(network) if self.wireless_enabled => wifi::strength_icon(network.strength),
+            _ => "wifidisconnected",
+        });
+        let blue_icon = icon_path(if self.bluetooth_powered {
+            "bluetooth-on-alt"
+        } else {
+            "bluetooth-off"
+        });
+        let plane_icon = icon_path(if self.airplane_mode {
+            "airplane-on"
+        } else {
+            "airplane-off"
+        });
+        let power_icon = icon_path("power-mode");
+        let fan_icon = icon_path("fan");
+        let volume_icon = icon_path(match self.master_volume {
+            _ if self.is_muted => "volume-mute",
+            0 => "volume-mute",
+            1..=33 => "volume-down",
+            34..=66 => "volume-middle",
+            _ => "volume-up",
+        });
+        let bright_icon = icon_path("brightness");
+        let devices_icon = icon_path(if self.is_audio_devices_open {
+            "chevron-up"
+        } else {
+            "chevron-down"
+        });
+        let mixer_icon = icon_path("mixer");
+        let mic_icon = icon_path(if self.is_mic_muted {
+            "mic-off"
+        } else {
+            "mic-on"
+        });
+        let shutdown_icon = icon_path("power");
+        let caffeine_icon = icon_path("clock-alt");
+        let is_quiet = self
+            .do_not_disturb
+            .is_quiet(binding::notifications::TimeOfDay::now());
+        let bell_icon = icon_path(if is_quiet { "do_not_disturb" } else { "bell" });
+
+        // TODO: Add power profile dropdown menu, change background to transparent, add degraded performance to power profile
         let icon = |icon_path| svg(svg::Handle::from_path(icon_path)).width(25).height(25);
 
         let circular_button = |icon_path| {
@@ -142,11 +1266,228 @@ impl iced_layershell::Application for ControlCenter {
                 .spacing(20)
                 .align_items(Alignment::Center),
             )
-            .on_press(message)
+            .on_press_maybe(message)
             .width(250)
             .padding([10, 20, 10, 20])
         };
 
+        let external_monitors = column(self.external_monitors.iter().enumerate().map(
+            |(index, monitor)| {
+                row![
+                    icon(&bright_icon),
+                    column![
+                        text(&monitor.name).size(12),
+                        slider(
+                            0..=monitor.max_brightness,
+                            monitor.brightness,
+                            move |value| { Message::SetExternalBrightness(index, value) }
+                        )
+                        .on_release(Message::ReleaseExternalBrightness(index)),
+                    ]
+                    .spacing(5),
+                ]
+                .align_items(Alignment::Center)
+                .spacing(10)
+                .into()
+            },
+        ))
+        .spacing(10);
+
+        let power_actions: Vec<_> = self
+            .power_actions
+            .iter()
+            .map(|&action| {
+                let name = match action {
+                    binding::logind::PowerAction::Lock => "lock",
+                    binding::logind::PowerAction::LogOut => "logout",
+                    binding::logind::PowerAction::Suspend => "moon",
+                    binding::logind::PowerAction::Hibernate => "power-alt",
+                    binding::logind::PowerAction::Reboot => "restart",
+                    binding::logind::PowerAction::PowerOff => "power",
+                };
+                (action, icon_path(name))
+            })
+            .collect();
+
+        let power_menu: Element<Message, Self::Theme> = match self.pending_power_action {
+            _ if !self.is_power_menu_open => column![].into(),
+            Some(action) => row![
+                container(text(format!("{action} now?")).font(styling::font::SF_PRO_BOLD))
+                    .width(Length::Fill),
+                button(text("Cancel")).on_press(Message::CancelPowerAction),
+                button(text(action.to_string()))
+                    .style(styling::style::Button::Selected)
+                    .on_press(Message::ConfirmPowerAction),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center)
+            .into(),
+            None => row(power_actions.iter().map(|(action, icon_path)| {
+                circular_button(icon_path)
+                    .on_press(Message::RequestPowerAction(*action))
+                    .into()
+            }))
+            .spacing(10)
+            .into(),
+        };
+
+        let microphone: Element<Message, Self::Theme> = match self.mic_volume {
+            Some(mic_volume) => row![
+                button(icon(&mic_icon))
+                    .style(styling::style::Button::Icon)
+                    .padding(0)
+                    .on_press(Message::ToggleMicMute),
+                slider(0..=100, mic_volume, Message::SetMicVolume)
+                    .on_release(Message::ReleaseMicVolume),
+            ]
+            .align_items(Alignment::Center)
+            .spacing(10)
+            .into(),
+            None => column![].into(),
+        };
+
+        let recording: Element<Message, Self::Theme> = if self.recording_applications.is_empty() {
+            column![].into()
+        } else {
+            text(format!(
+                "Microphone in use by {}",
+                self.recording_applications.join(", ")
+            ))
+            .size(12)
+            .into()
+        };
+
+        let audio_devices: Element<Message, Self::Theme> = if self.is_audio_devices_open {
+            let device_list = |title, direction, devices: &[binding::pulse::AudioDevice]| {
+                column![text(title).font(styling::font::SF_PRO_BOLD)]
+                    .extend(devices.iter().map(|device| {
+                        button(text(&device.description))
+                            .style(if device.is_default {
+                                styling::style::Button::Selected
+                            } else {
+                                styling::style::Button::Default
+                            })
+                            .on_press(Message::SetDefaultAudioDevice(
+                                direction,
+                                device.name.clone(),
+                            ))
+                            .width(Length::Fill)
+                            .into()
+                    }))
+                    .spacing(5)
+            };
+
+            column![
+                device_list("Output", binding::pulse::Direction::Output, &self.sinks),
+                device_list("Input", binding::pulse::Direction::Input, &self.sources),
+                button(text("Move playing streams"))
+                    .style(if self.move_streams {
+                        styling::style::Button::Selected
+                    } else {
+                        styling::style::Button::Default
+                    })
+                    .on_press(Message::ToggleMoveStreams),
+            ]
+            .spacing(10)
+            .into()
+        } else {
+            column![].into()
+        };
+
+        let system_stats: Element<Message, Self::Theme> = match &self.system_stats {
+            Some(stats) => system_monitor::view(stats),
+            None => column![].into(),
+        };
+
+        let media_player: Element<Message, Self::Theme> =
+            match self.media_players.get(self.active_media_player) {
+                Some(player) => media::view(player, self.media_players.len()),
+                None => column![].into(),
+            };
+
+        let inhibitors = column(self.inhibitors.iter().map(|inhibitor| {
+            text(format!(
+                "{} blocks {}: {}",
+                inhibitor.who, inhibitor.what, inhibitor.why
+            ))
+            .size(12)
+            .into()
+        }))
+        .spacing(5);
+
+        let wifi_status = match &self.wifi {
+            _ if !self.is_wifi_available => "No Wi-Fi adapter".to_string(),
+            _ if !self.wireless_enabled => "Wi-Fi is off".to_string(),
+            _ if self.hotspot.is_some() => "Sharing a hotspot".to_string(),
+            Some(network) => format!("{} ({}%)", network.ssid, network.strength),
+            None => "Not connected".to_string(),
+        };
+
+        let fan_profile = match &self.platform_profile {
+            Some(binding::platform_profile::PlatformProfileInfo::Available { active, .. }) => {
+                binding::platform_profile::display_name(active)
+            }
+            Some(binding::platform_profile::PlatformProfileInfo::NotAvailable) => {
+                "Unavailable".to_string()
+            }
+            None => "…".to_string(),
+        };
+
+        let fan_profile_error: Element<Message, Self::Theme> = match &self.platform_profile_error {
+            Some(error) => text(error).size(12).into(),
+            None => column![].into(),
+        };
+
+        let airplane_status = match self.airplane_mode {
+            _ if !self.is_airplane_available => "No radios",
+            _ if self.airplane_hard_blocked => "Blocked by a hardware switch",
+            true => "Airplane mode is on",
+            false => "Airplane mode is off",
+        };
+
+        let notification_status = match self.notifications.len() {
+            _ if self.notification_server.is_none() => {
+                "Another notification daemon is running".to_string()
+            }
+            0 => "No notifications".to_string(),
+            1 => "1 notification".to_string(),
+            count => format!("{count} notifications"),
+        };
+        let notification_status = if is_quiet {
+            format!("{notification_status}, Do Not Disturb")
+        } else {
+            notification_status
+        };
+
+        let signal_icon = self.modem.as_ref().map(|modem| {
+            icon_path(match modem.signal {
+                0..=33 => "signal-low",
+                34..=66 => "signal-mid",
+                _ => "signal-full",
+            })
+        });
+
+        let mut network_status = row![].spacing(20).align_items(Alignment::Center);
+        if let Some(wired) = &self.wired {
+            let status = match wired.speed {
+                _ if !wired.carrier => "unplugged".to_string(),
+                0 => "connected".to_string(),
+                speed if speed % 1000 == 0 => format!("{} Gb/s", speed / 1000),
+                speed => format!("{speed} Mb/s"),
+            };
+            network_status = network_status.push(text(format!("{}: {status}", wired.interface)));
+        }
+        if let (Some(modem), Some(signal_icon)) = (&self.modem, &signal_icon) {
+            network_status = network_status.push(
+                row![
+                    icon(signal_icon),
+                    text(format!("{} ({}%)", modem.operator, modem.signal)),
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center),
+            );
+        }
+
         let battery = row![
             icon(&battery_icon),
             column![
@@ -157,73 +1498,291 @@ impl iced_layershell::Application for ControlCenter {
         .spacing(10)
         .align_items(Alignment::Center);
 
-        container(
-            column![
+        let content: Element<Message, Self::Theme> = match self.page {
+            Page::Main => column![
                 row![
                     container(battery).width(Length::Fill),
                     container(
                         row![
-                            circular_button(&wifi_icon),
-                            circular_button(&blue_icon),
-                            circular_button(&plane_icon),
+                            tooltip(
+                                circular_button(&wifi_icon).on_press_maybe(
+                                    self.is_wifi_available
+                                        .then_some(Message::ShowPage(Page::Wifi))
+                                ),
+                                text(wifi_status).size(12),
+                                tooltip::Position::Bottom
+                            )
+                            .style(styling::style::Container::Rounded)
+                            .padding(10),
+                            circular_button(&blue_icon).on_press_maybe(
+                                self.is_bluetooth_available
+                                    .then_some(Message::ShowPage(Page::Bluetooth))
+                            ),
+                            tooltip(
+                                circular_button(&plane_icon).on_press_maybe(
+                                    self.is_airplane_available
+                                        .then_some(Message::ToggleAirplaneMode)
+                                ),
+                                text(airplane_status).size(12),
+                                tooltip::Position::Bottom
+                            )
+                            .style(styling::style::Container::Rounded)
+                            .padding(10),
+                            tooltip(
+                                circular_button(&bell_icon)
+                                    .on_press(Message::ShowPage(Page::Notifications)),
+                                text(notification_status).size(12),
+                                tooltip::Position::Bottom
+                            )
+                            .style(styling::style::Container::Rounded)
+                            .padding(10),
+                            circular_button(&shutdown_icon).on_press(Message::TogglePowerMenu),
                         ]
                         .spacing(10)
                     )
                 ]
                 .align_items(Alignment::Center),
+                network_status,
+                vpn::view(&self.vpns, self.vpn_error.as_deref()),
                 container(
                     column![
                         row![
-                            icon(&volume_icon),
+                            button(icon(&volume_icon))
+                                .style(styling::style::Button::Icon)
+                                .padding(0)
+                                .on_press(Message::ToggleMute),
                             slider(0..=100, self.master_volume, Message::SetMasterVolume)
+                                .on_release(Message::ReleaseMasterVolume),
+                            button(icon(&devices_icon))
+                                .style(styling::style::Button::Icon)
+                                .padding(0)
+                                .on_press(Message::ToggleAudioDevices),
+                            button(icon(&mixer_icon))
+                                .style(styling::style::Button::Icon)
+                                .padding(0)
+                                .on_press(Message::ShowPage(Page::Mixer)),
                         ]
                         .align_items(Alignment::Center)
                         .spacing(10),
+                        audio_devices,
+                        microphone,
+                        recording,
                         row![
                             icon(&bright_icon),
                             slider(
                                 self.min_brightness..=self.max_brightness,
                                 self.current_brightness,
                                 Message::SetBrightness
-                            ),
+                            )
+                            .on_release(Message::ReleaseBrightness),
+                            button(text("Auto"))
+                                .style(if self.auto_brightness {
+                                    styling::style::Button::Selected
+                                } else {
+                                    styling::style::Button::Default
+                                })
+                                .on_press(Message::ToggleAutoBrightness),
                         ]
                         .align_items(Alignment::Center)
-                        .spacing(10)
+                        .spacing(10),
+                        external_monitors,
                     ]
                     .spacing(10)
                 ),
+                media_player,
+                system_stats,
+                storage::view(
+                    &self.filesystems,
+                    &self.removable_drives,
+                    self.storage_pending.as_ref(),
+                    self.storage_error.as_deref(),
+                ),
                 container(
                     column![
                         rectangular_button(
                             "Power Mode",
                             self.active_power_profile.to_string(),
                             &power_icon,
-                            Message::ToggleProfiles
+                            Some(Message::ToggleProfiles)
                         ),
                         rectangular_button(
                             "Fan Profile",
-                            "Silent".to_string(),
+                            fan_profile,
                             &fan_icon,
-                            Message::ToggleProfiles
+                            Some(Message::CycleFanProfile)
+                        ),
+                        fan_profile_error,
+                        thermal::view(&self.sensors, &self.raised_thermal_alerts),
+                        rectangular_button(
+                            "Caffeine",
+                            match self.caffeine {
+                                _ if self.caffeine_pending => "…",
+                                Some(_) => "On",
+                                None => "Off",
+                            }
+                            .to_string(),
+                            &caffeine_icon,
+                            (!self.caffeine_pending).then_some(Message::ToggleCaffeine)
                         ),
+                        inhibitors,
                     ]
                     .spacing(10)
-                )
+                ),
+                power_menu,
             ]
-            .spacing(20),
-        )
-        .style(styling::style::Container::HeavyRounded)
-        .padding(32)
-        .width(iced::Length::Fill)
-        .height(iced::Length::Fill)
-        .into()
+            .spacing(20)
+            .into(),
+            Page::Mixer => mixer::view(&self.playback_streams),
+            Page::Wifi => {
+                let networks = wifi::view(
+                    self.wireless_enabled,
+                    self.wifi.as_ref(),
+                    &self.wifi_networks,
+                    self.wifi_prompt.as_ref(),
+                    &self.wifi_passphrase,
+                    self.wifi_error.as_deref(),
+                );
+
+                if self.wireless_enabled {
+                    column![
+                        networks,
+                        wifi::hotspot(
+                            self.hotspot.as_ref(),
+                            &self.hotspot_config,
+                            self.hotspot_clients,
+                            self.hotspot_error.as_deref(),
+                        ),
+                    ]
+                    .spacing(20)
+                    .into()
+                } else {
+                    networks
+                }
+            }
+            Page::Processes => processes::view(
+                &self.processes,
+                self.process_sort,
+                self.pending_process_signal.as_ref(),
+                self.process_error.as_deref(),
+            ),
+            Page::Notifications => notifications::view(&self.notifications, &self.do_not_disturb),
+            Page::Bluetooth => bluetooth::view(
+                self.bluetooth_powered,
+                &self.bluetooth_devices,
+                &self.bluetooth_discovered,
+                self.bluetooth_discovering,
+                self.bluetooth_pending.as_ref(),
+                self.bluetooth_error.as_deref(),
+            ),
+        };
+
+        let content: Element<Message, Self::Theme> = match &self.pairing_request {
+            Some(request) => column![bluetooth::pairing_dialog(request), content]
+                .spacing(20)
+                .into(),
+            None => content,
+        };
+
+        container(content)
+            .style(styling::style::Container::HeavyRounded)
+            .padding(32)
+            .width(iced::Length::Fill)
+            .height(iced::Length::Fill)
+            .into()
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
+        // The light sensor is only claimed while auto-brightness is on.
+        let ambient_light = if self.auto_brightness {
+            binding::iio::subscription(self.generation).map(Message::AmbientLightSensor)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let bluetooth_discovery = if self.page == Page::Bluetooth
+            && self.bluetooth_discovering
+            && self.bluetooth_powered
+        {
+            binding::bluez::discovery_subscription(self.generation).map(Message::BluetoothDiscovery)
+        } else {
+            iced::Subscription::none()
+        };
+
+        // We only stand in for the default agent while pairing, so other
+        // devices' requests don't pop up out of nowhere the rest of the time.
+        let is_pairing = self.page == Page::Bluetooth
+            && (self.bluetooth_discovering || self.bluetooth_pending.is_some());
+        let bluetooth_agent = if is_pairing {
+            binding::bluez::agent_subscription(self.generation).map(Message::BluetoothAgent)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let processes = if self.page == Page::Processes {
+            binding::system_monitor::process_subscription(self.generation, self.monitor_config)
+                .map(Message::Processes)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let is_media_playing = self
+            .media_players
+            .iter()
+            .any(|player| player.status == binding::mpris::PlaybackStatus::Playing);
+        let media_tick = if is_media_playing {
+            iced::time::every(std::time::Duration::from_secs(1)).map(|_| Message::MediaTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let hotspot_tick = if self.hotspot.is_some() {
+            iced::time::every(std::time::Duration::from_secs(5)).map(|_| Message::HotspotTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let notification_tick = if self.popups.iter().any(|popup| popup.expires.is_some()) {
+            iced::time::every(std::time::Duration::from_millis(500))
+                .map(|_| Message::NotificationTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        // Sensors don't announce anything, so they're read now and then.
+        let sensor_tick =
+            iced::time::every(std::time::Duration::from_secs(3)).map(|_| Message::SensorTick);
+        // Neither does disk usage, which changes slowly anyway.
+        let filesystem_tick =
+            iced::time::every(std::time::Duration::from_secs(30)).map(|_| Message::FilesystemTick);
+
         iced::Subscription::batch([
-            binding::upower::subscription(0).map(Message::UPowerDevice),
-            binding::hadess::subscription(0).map(Message::HadessDevice),
-            binding::logind::subscription(0).map(Message::ScreenDevice),
+            binding::upower::subscription(self.generation).map(Message::UPowerDevice),
+            binding::hadess::subscription(self.generation).map(Message::HadessDevice),
+            binding::platform_profile::subscription(self.generation).map(Message::PlatformProfile),
+            binding::logind::subscription(self.generation).map(Message::ScreenDevice),
+            binding::logind::session_subscription(0).map(Message::Session),
+            binding::networkmanager::subscription(self.generation).map(Message::Network),
+            binding::bluez::subscription(self.generation).map(Message::Bluetooth),
+            binding::rfkill::subscription(self.generation).map(Message::Rfkill),
+            binding::udisks::subscription(self.generation).map(Message::Storage),
+            binding::system_monitor::subscription(self.generation, self.monitor_config)
+                .map(Message::SystemStats),
+            binding::pulse::subscription(self.generation).map(Message::AudioDevice),
+            binding::mpris::subscription(self.generation).map(Message::MediaPlayers),
+            // The session bus doesn't go anywhere on suspend.
+            binding::notifications::subscription(0).map(Message::Notifications),
+            self.popup_link
+                .as_ref()
+                .map_or_else(iced::Subscription::none, popups::PanelLink::subscription),
+            ambient_light,
+            bluetooth_discovery,
+            bluetooth_agent,
+            processes,
+            media_tick,
+            hotspot_tick,
+            notification_tick,
+            sensor_tick,
+            filesystem_tick,
         ])
     }
 }
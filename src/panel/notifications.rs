@@ -0,0 +1,173 @@
+use super::{icon_path, Message, Page};
+use crate::binding::notifications::{DoNotDisturb, Notification, TimeOfDay, Urgency};
+use crate::styling;
+
+use iced::{
+    widget::{button, column, container, row, scrollable, svg, text, Row},
+    Alignment, Element, Length,
+};
+use std::time::{Instant, SystemTime};
+
+/// Older notifications are dropped from the history past this.
+pub const HISTORY_LIMIT: usize = 50;
+/// More popups than this and only the newest are shown.
+const VISIBLE_POPUPS: usize = 3;
+
+/// A notification currently shown as a popup.
+#[derive(Debug, Clone, Copy)]
+pub struct Popup {
+    pub id: u32,
+    /// `None` if it stays up until dismissed.
+    pub expires: Option<Instant>,
+}
+
+fn age(received: SystemTime) -> String {
+    let minutes = received.elapsed().unwrap_or_default().as_secs() / 60;
+
+    match minutes {
+        0 => "now".to_string(),
+        1..=59 => format!("{minutes} min ago"),
+        60..=1439 => format!("{} h ago", minutes / 60),
+        _ => format!("{} d ago", minutes / 1440),
+    }
+}
+
+/// `dismiss` is sent by the close button, which only hides popups but
+/// removes notifications from the history.
+fn card<'a>(
+    notification: &'a Notification,
+    dismiss: Message,
+) -> Element<'a, Message, styling::theme::Theme> {
+    let header = row![
+        svg(svg::Handle::from_path(icon_path(
+            if notification.urgency == Urgency::Critical {
+                "bell"
+            } else {
+                "comment"
+            }
+        )))
+        .width(20)
+        .height(20),
+        text(&notification.app_name).size(12).width(Length::Fill),
+        text(age(notification.received)).size(12),
+        button(
+            svg(svg::Handle::from_path(icon_path("cross")))
+                .width(15)
+                .height(15)
+        )
+        .style(styling::style::Button::Icon)
+        .padding(0)
+        .on_press(dismiss),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let mut content = column![
+        header,
+        text(&notification.summary).font(styling::font::SF_PRO_BOLD)
+    ]
+    .spacing(5);
+
+    if !notification.body.is_empty() {
+        content = content.push(text(&notification.body).size(12));
+    }
+
+    if !notification.actions.is_empty() {
+        content = content.push(
+            Row::with_children(notification.actions.iter().map(|(key, label)| {
+                button(text(label).size(12))
+                    .on_press(Message::InvokeNotificationAction(
+                        notification.id,
+                        key.clone(),
+                    ))
+                    .into()
+            }))
+            .spacing(10),
+        );
+    }
+
+    container(content)
+        .style(styling::style::Container::Rounded)
+        .padding(15)
+        .width(Length::Fill)
+        .into()
+}
+
+/// The newest of `popups`, which come oldest first, stacked on top.
+pub fn popups(popups: &[Notification]) -> Element<'_, Message, styling::theme::Theme> {
+    column(
+        popups
+            .iter()
+            .rev()
+            .take(VISIBLE_POPUPS)
+            .map(|notification| card(notification, Message::HidePopup(notification.id))),
+    )
+    .spacing(10)
+    .into()
+}
+
+/// `notifications` are newest first.
+pub fn view<'a>(
+    notifications: &'a [Notification],
+    do_not_disturb: &'a DoNotDisturb,
+) -> Element<'a, Message, styling::theme::Theme> {
+    let header = row![
+        button(
+            svg(svg::Handle::from_path(icon_path("chevron-left")))
+                .width(25)
+                .height(25)
+        )
+        .style(styling::style::Button::Icon)
+        .padding(0)
+        .on_press(Message::ShowPage(Page::Main)),
+        text("Notifications")
+            .font(styling::font::SF_PRO_BOLD)
+            .size(16)
+            .width(Length::Fill),
+        button(
+            svg(svg::Handle::from_path(icon_path("do_not_disturb")))
+                .width(20)
+                .height(20)
+        )
+        .style(if do_not_disturb.enabled {
+            styling::style::Button::Selected
+        } else {
+            styling::style::Button::Default
+        })
+        .on_press(Message::ToggleDoNotDisturb),
+        button(text("Clear"))
+            .on_press_maybe((!notifications.is_empty()).then_some(Message::ClearNotifications)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let list: Element<Message, styling::theme::Theme> = if notifications.is_empty() {
+        text("No notifications").into()
+    } else {
+        scrollable(
+            column(notifications.iter().map(|notification| {
+                card(notification, Message::DismissNotification(notification.id))
+            }))
+            .spacing(10),
+        )
+        .height(Length::Fill)
+        .into()
+    };
+
+    let time = TimeOfDay::now();
+    let status: Element<Message, styling::theme::Theme> = if do_not_disturb.enabled {
+        text("Do Not Disturb is on, notifications only go to the history")
+            .size(12)
+            .into()
+    } else if let Some(schedule) = do_not_disturb.active_schedule(time) {
+        text(format!("Quiet hours until {}", schedule.end))
+            .size(12)
+            .into()
+    } else {
+        column![].into()
+    };
+
+    container(column![header, status, list].spacing(20))
+        .width(Length::Fill)
+        .into()
+}
@@ -0,0 +1,153 @@
+use super::{notifications, Message};
+use crate::binding::notifications::Notification;
+use crate::styling;
+
+use iced::{
+    futures::{self, channel::mpsc, StreamExt},
+    widget::container,
+    Command, Element, Length,
+};
+use std::sync::{Arc, Mutex};
+
+/// The receiving half of a channel, which the subscription reading it takes
+/// when it starts.
+struct Inbox<T>(Arc<Mutex<Option<mpsc::UnboundedReceiver<T>>>>);
+
+impl<T> Clone for Inbox<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: 'static + Send> Inbox<T> {
+    fn new(receiver: mpsc::UnboundedReceiver<T>) -> Self {
+        Self(Arc::new(Mutex::new(Some(receiver))))
+    }
+
+    fn subscription(&self, id: &'static str) -> iced::Subscription<T> {
+        let receiver = self.0.lock().ok().and_then(|mut receiver| receiver.take());
+
+        iced::subscription::run_with_id(
+            id,
+            match receiver {
+                Some(receiver) => receiver.boxed(),
+                // Already being read.
+                None => futures::stream::pending().boxed(),
+            },
+        )
+    }
+}
+
+/// The control center's end: it owns the notifications and decides which
+/// pop up, the popups send back what the user clicked.
+#[derive(Clone)]
+pub struct PanelLink {
+    shown: mpsc::UnboundedSender<Vec<Notification>>,
+    clicked: Inbox<Message>,
+}
+
+impl PanelLink {
+    /// Replaces whatever the popups showed with `notifications`, newest last.
+    pub fn show(&self, notifications: Vec<Notification>) {
+        let _ = self.shown.unbounded_send(notifications);
+    }
+
+    pub fn subscription(&self) -> iced::Subscription<Message> {
+        self.clicked.subscription("popup-clicks")
+    }
+}
+
+impl std::fmt::Debug for PanelLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PanelLink").finish_non_exhaustive()
+    }
+}
+
+/// The popups' end.
+pub struct PopupLink {
+    shown: Inbox<Vec<Notification>>,
+    clicked: mpsc::UnboundedSender<Message>,
+}
+
+/// Both ends, for the two surfaces to be started with.
+pub fn link() -> (PanelLink, PopupLink) {
+    let (shown, shown_receiver) = mpsc::unbounded();
+    let (clicked, clicked_receiver) = mpsc::unbounded();
+
+    (
+        PanelLink {
+            shown,
+            clicked: Inbox::new(clicked_receiver),
+        },
+        PopupLink {
+            shown: Inbox::new(shown_receiver),
+            clicked,
+        },
+    )
+}
+
+/// Popups on a layer surface of their own, anchored to a corner, so they
+/// show up whether the control center is open or not.
+pub struct NotificationPopups {
+    link: PopupLink,
+    notifications: Vec<Notification>,
+}
+
+#[derive(Debug, Clone)]
+pub enum PopupMessage {
+    Show(Vec<Notification>),
+    /// Boxed, a panel message being much larger than the rest.
+    Clicked(Box<Message>),
+}
+
+impl iced_layershell::Application for NotificationPopups {
+    type Executor = iced::executor::Default;
+    type Message = PopupMessage;
+    type Theme = styling::theme::Theme;
+    type Flags = PopupLink;
+
+    fn new(link: PopupLink) -> (Self, Command<PopupMessage>) {
+        (
+            Self {
+                link,
+                notifications: Vec::new(),
+            },
+            Command::none(),
+        )
+    }
+
+    fn namespace(&self) -> String {
+        "morpheus-popups".to_string()
+    }
+
+    fn update(&mut self, message: PopupMessage) -> Command<PopupMessage> {
+        match message {
+            PopupMessage::Show(notifications) => {
+                self.notifications = notifications;
+            }
+            // The control center answers with what's left to show.
+            PopupMessage::Clicked(message) => {
+                let _ = self.link.clicked.unbounded_send(*message);
+            }
+        }
+
+        Command::none()
+    }
+
+    fn view(&self) -> Element<'_, PopupMessage, Self::Theme> {
+        // The background is transparent, so nothing shows without popups.
+        container(
+            notifications::popups(&self.notifications)
+                .map(|message| PopupMessage::Clicked(Box::new(message))),
+        )
+        .width(Length::Fill)
+        .into()
+    }
+
+    fn subscription(&self) -> iced::Subscription<PopupMessage> {
+        self.link
+            .shown
+            .subscription("popups-shown")
+            .map(PopupMessage::Show)
+    }
+}
@@ -0,0 +1,112 @@
+use super::{icon_path, Message, Page};
+use crate::binding::system_monitor::{Process, ProcessSignal, ProcessSort};
+use crate::styling;
+
+use iced::{
+    widget::{button, column, container, row, scrollable, svg, text},
+    Alignment, Element, Length,
+};
+
+/// How many of the heaviest processes are listed.
+const TOP: usize = 20;
+
+/// `processes` are sorted by `sort` already. `pending` is the signal waiting
+/// to be confirmed, if any.
+pub fn view<'a>(
+    processes: &'a [Process],
+    sort: ProcessSort,
+    pending: Option<&'a (Process, ProcessSignal)>,
+    error: Option<&'a str>,
+) -> Element<'a, Message, styling::theme::Theme> {
+    let sort_button = |label, by| {
+        button(text(label))
+            .style(if sort == by {
+                styling::style::Button::Selected
+            } else {
+                styling::style::Button::Default
+            })
+            .on_press(Message::SortProcesses(by))
+    };
+
+    let header = row![
+        button(
+            svg(svg::Handle::from_path(icon_path("chevron-left")))
+                .width(25)
+                .height(25)
+        )
+        .style(styling::style::Button::Icon)
+        .padding(0)
+        .on_press(Message::ShowPage(Page::Main)),
+        text("Processes")
+            .font(styling::font::SF_PRO_BOLD)
+            .size(16)
+            .width(Length::Fill),
+        sort_button("CPU", ProcessSort::Cpu),
+        sort_button("Memory", ProcessSort::Memory),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let confirmation: Element<Message, styling::theme::Theme> = match pending {
+        Some((process, signal)) => row![
+            container(
+                text(format!("{signal} {} ({})?", process.name, process.pid))
+                    .font(styling::font::SF_PRO_BOLD)
+            )
+            .width(Length::Fill),
+            button(text("Cancel")).on_press(Message::CancelProcessSignal),
+            button(text(signal.to_string()))
+                .style(styling::style::Button::Selected)
+                .on_press(Message::ConfirmProcessSignal),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into(),
+        None => column![].into(),
+    };
+
+    let error: Element<Message, styling::theme::Theme> = match error {
+        Some(error) => text(error).size(12).into(),
+        None => column![].into(),
+    };
+
+    let list: Element<Message, styling::theme::Theme> = if processes.is_empty() {
+        text("Measuring…").into()
+    } else {
+        scrollable(
+            column(processes.iter().take(TOP).map(|process| {
+                row![
+                    column![
+                        text(&process.name),
+                        text(format!(
+                            "{} · {:.0}% · {:.0} MiB",
+                            process.pid,
+                            process.cpu * 100.0,
+                            process.memory as f64 / 1024.0
+                        ))
+                        .size(12),
+                    ]
+                    .width(Length::Fill),
+                    button(text("Terminate").size(12)).on_press(Message::RequestProcessSignal(
+                        process.clone(),
+                        ProcessSignal::Terminate
+                    )),
+                    button(text("Kill").size(12)).on_press(Message::RequestProcessSignal(
+                        process.clone(),
+                        ProcessSignal::Kill
+                    )),
+                ]
+                .spacing(10)
+                .align_items(Alignment::Center)
+                .into()
+            }))
+            .spacing(10),
+        )
+        .height(Length::Fill)
+        .into()
+    };
+
+    container(column![header, confirmation, error, list].spacing(20))
+        .width(Length::Fill)
+        .into()
+}
@@ -0,0 +1,140 @@
+use super::{icon_path, Message};
+use crate::binding::{
+    mounts::Filesystem,
+    udisks::{RemovableDrive, Volume},
+};
+use crate::styling;
+
+use iced::{
+    widget::{button, column, container, progress_bar, row, svg, text},
+    Alignment, Element, Length,
+};
+use zbus::zvariant::OwnedObjectPath;
+
+/// `1.5 GB`, in powers of 1000 like file managers do.
+fn format_size(bytes: u64) -> String {
+    let units = ["B", "kB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+
+    while size >= 1000.0 && unit < units.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    format!("{size:.1} {}", units[unit])
+}
+
+/// `pending` is the drive or volume we're waiting on, if any.
+pub fn view<'a>(
+    filesystems: &'a [Filesystem],
+    drives: &'a [RemovableDrive],
+    pending: Option<&'a OwnedObjectPath>,
+    error: Option<&'a str>,
+) -> Element<'a, Message, styling::theme::Theme> {
+    if filesystems.is_empty() && drives.is_empty() {
+        return column![].into();
+    }
+
+    let icon = |name: &str| {
+        svg(svg::Handle::from_path(icon_path(name)))
+            .width(20)
+            .height(20)
+    };
+
+    let filesystems = column(filesystems.iter().map(|filesystem| {
+        column![
+            row![
+                icon("folder"),
+                text(&filesystem.mount_point).width(Length::Fill),
+                text(format!(
+                    "{} / {}",
+                    format_size(filesystem.used),
+                    format_size(filesystem.total)
+                ))
+                .size(12),
+            ]
+            .spacing(10)
+            .align_items(Alignment::Center),
+            progress_bar(0.0..=1.0, filesystem.fraction() as f32).height(6),
+        ]
+        .spacing(5)
+        .into()
+    }))
+    .spacing(10);
+
+    let volume_row = |volume: &'a Volume| {
+        let is_pending = pending.is_some_and(|path| *path == volume.path);
+        let (status, action, message) = match &volume.mount_point {
+            Some(mount_point) => (
+                mount_point.clone(),
+                "Unmount",
+                Message::UnmountVolume(volume.clone()),
+            ),
+            None => (
+                "Not mounted".to_string(),
+                "Mount",
+                Message::MountVolume(volume.clone()),
+            ),
+        };
+
+        row![
+            column![
+                text(format!("{} ({})", volume.label, format_size(volume.size))),
+                text(if is_pending {
+                    "…".to_string()
+                } else {
+                    status
+                })
+                .size(12),
+            ]
+            .width(Length::Fill),
+            button(text(action).size(12)).on_press_maybe(pending.is_none().then_some(message)),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center)
+        .into()
+    };
+
+    let drives = column(drives.iter().map(|drive| {
+        let header = row![
+            icon("usb"),
+            text(&drive.name)
+                .font(styling::font::SF_PRO_BOLD)
+                .width(Length::Fill),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center);
+
+        let header = if drive.ejectable {
+            let is_pending = pending.is_some_and(|path| *path == drive.path);
+            header.push(
+                button(text(if is_pending { "Ejecting…" } else { "Eject" }).size(12))
+                    .on_press_maybe(
+                        pending
+                            .is_none()
+                            .then(|| Message::EjectDrive(drive.clone())),
+                    ),
+            )
+        } else {
+            header
+        };
+
+        column![header]
+            .extend(drive.volumes.iter().map(volume_row))
+            .spacing(5)
+            .into()
+    }))
+    .spacing(10);
+
+    let error: Element<Message, styling::theme::Theme> = match error {
+        Some(error) => text(error).size(12).into(),
+        None => column![].into(),
+    };
+
+    container(column![filesystems, drives, error].spacing(15))
+        .style(styling::style::Container::Rounded)
+        .padding(15)
+        .width(Length::Fill)
+        .into()
+}
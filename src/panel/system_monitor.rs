@@ -0,0 +1,106 @@
+use super::{icon_path, Message, Page};
+use crate::binding::system_monitor::{Memory, SystemStats};
+use crate::styling;
+
+use iced::{
+    widget::{button, column, container, progress_bar, row, svg, text, Row, Space},
+    Alignment, Element, Length,
+};
+
+const SPARKLINE_HEIGHT: f32 = 30.0;
+const CORES_PER_ROW: usize = 4;
+
+/// One bar per sample, oldest on the left.
+fn sparkline<'a>(history: &[f64]) -> Element<'a, Message, styling::theme::Theme> {
+    Row::with_children(history.iter().map(|value| {
+        // A sliver even when idle, so the line doesn't vanish.
+        let height = (value.clamp(0.0, 1.0) as f32 * SPARKLINE_HEIGHT).max(1.0);
+
+        container(Space::new(Length::Fill, height))
+            .style(styling::style::Container::Bar)
+            .width(Length::Fill)
+            .into()
+    }))
+    .spacing(1)
+    .height(SPARKLINE_HEIGHT)
+    .align_items(Alignment::End)
+    .into()
+}
+
+fn gibibytes(kibibytes: u64) -> f64 {
+    kibibytes as f64 / (1024.0 * 1024.0)
+}
+
+fn memory_bar<'a>(
+    icon: &str,
+    name: &str,
+    memory: Memory,
+) -> Element<'a, Message, styling::theme::Theme> {
+    column![
+        row![
+            svg(svg::Handle::from_path(icon_path(icon)))
+                .width(20)
+                .height(20),
+            text(format!(
+                "{name} {:.1} / {:.1} GiB",
+                gibibytes(memory.used),
+                gibibytes(memory.total)
+            ))
+            .size(12),
+        ]
+        .spacing(10)
+        .align_items(Alignment::Center),
+        progress_bar(0.0..=1.0, memory.fraction() as f32).height(6),
+    ]
+    .spacing(5)
+    .into()
+}
+
+pub fn view(stats: &SystemStats) -> Element<'_, Message, styling::theme::Theme> {
+    let [one, five, fifteen] = stats.load;
+
+    let header = row![
+        svg(svg::Handle::from_path(icon_path("system_monitor")))
+            .width(20)
+            .height(20),
+        text(format!("CPU {:.0}%", stats.cpu * 100.0))
+            .font(styling::font::SF_PRO_BOLD)
+            .width(Length::Fill),
+        text(format!("Load {one:.2} {five:.2} {fifteen:.2}")).size(12),
+        button(text("Processes").size(12)).on_press(Message::ShowPage(Page::Processes)),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let cores = column(stats.cores.chunks(CORES_PER_ROW).map(|chunk| {
+        Row::with_children(chunk.iter().map(|usage| {
+            progress_bar(0.0..=1.0, *usage as f32)
+                .height(4)
+                .width(Length::Fill)
+                .into()
+        }))
+        .spacing(5)
+        .into()
+    }))
+    .spacing(5);
+
+    let mut content = column![
+        header,
+        cores,
+        sparkline(&stats.cpu_history),
+        memory_bar("ram", "Memory", stats.memory),
+        sparkline(&stats.memory_history),
+    ]
+    .spacing(10);
+
+    // Plenty of machines have none at all.
+    if stats.swap.total > 0 {
+        content = content.push(memory_bar("ram", "Swap", stats.swap));
+    }
+
+    container(content)
+        .style(styling::style::Container::Rounded)
+        .padding(15)
+        .width(Length::Fill)
+        .into()
+}
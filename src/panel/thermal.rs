@@ -0,0 +1,84 @@
+use super::{icon_path, Message};
+use crate::binding::sensors::{SensorKind, SensorReadings, Temperature};
+use crate::styling;
+
+use iced::{
+    widget::{column, row, svg, text, tooltip},
+    Alignment, Element,
+};
+
+/// The hottest CPU, GPU and disk sensor along with the loudest fan; every
+/// sensor is in the tooltip. Nothing at all without any.
+pub fn view<'a>(
+    readings: &'a SensorReadings,
+    alerts: &'a [Temperature],
+) -> Element<'a, Message, styling::theme::Theme> {
+    if readings.temperatures.is_empty() && readings.fans.is_empty() {
+        return column![].into();
+    }
+
+    let icon = |icon_path: String| svg(svg::Handle::from_path(icon_path)).width(20).height(20);
+
+    let mut summary = row![].spacing(15).align_items(Alignment::Center);
+    for (kind, icon_name) in [
+        (SensorKind::Cpu, "cpu"),
+        (SensorKind::Gpu, "temperature"),
+        (SensorKind::Disk, "temperature"),
+    ] {
+        if let Some(temperature) = readings.hottest(kind) {
+            summary = summary.push(
+                row![
+                    icon(icon_path(icon_name)),
+                    text(format!("{kind} {:.0}°C", temperature.celsius)).size(12),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center),
+            );
+        }
+    }
+    if let Some(fan) = readings.loudest_fan() {
+        summary = summary.push(
+            row![
+                icon(icon_path("fan-alt")),
+                text(format!("{} RPM", fan.rpm)).size(12),
+            ]
+            .spacing(5)
+            .align_items(Alignment::Center),
+        );
+    }
+
+    let details = readings
+        .temperatures
+        .iter()
+        .map(|temperature| {
+            format!(
+                "{} {}: {:.0}°C",
+                temperature.chip, temperature.label, temperature.celsius
+            )
+        })
+        .chain(
+            readings
+                .fans
+                .iter()
+                .map(|fan| format!("{} {}: {} RPM", fan.chip, fan.label, fan.rpm)),
+        )
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    let summary = tooltip(summary, text(details).size(12), tooltip::Position::Bottom)
+        .style(styling::style::Container::Rounded)
+        .padding(10);
+
+    column![summary]
+        .extend(alerts.iter().map(|alert| {
+            text(format!(
+                "{} is running hot: {:.0}°C ({})",
+                alert.kind, alert.celsius, alert.label
+            ))
+            .font(styling::font::SF_PRO_BOLD)
+            .size(12)
+            .into()
+        }))
+        .spacing(5)
+        .into()
+}
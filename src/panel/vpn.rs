@@ -0,0 +1,57 @@
+use super::{icon_path, Message};
+use crate::binding::networkmanager::{VpnConnection, VpnState};
+use crate::styling;
+
+use iced::{
+    widget::{button, column, row, svg, text},
+    Alignment, Element, Length,
+};
+
+/// One toggle per VPN profile; nothing at all without any.
+pub fn view<'a>(
+    vpns: &'a [VpnConnection],
+    error: Option<&'a str>,
+) -> Element<'a, Message, styling::theme::Theme> {
+    let lock_icon = icon_path("lock");
+
+    let toggles = column(vpns.iter().map(|vpn| {
+        let status = match &vpn.state {
+            VpnState::Disconnected => vpn.kind.clone(),
+            VpnState::Activating => "Connecting…".to_string(),
+            VpnState::Connected => "Connected".to_string(),
+            VpnState::Deactivating => "Disconnecting…".to_string(),
+            VpnState::Failed(reason) => format!("Failed: {reason}"),
+        };
+        let is_up = matches!(vpn.state, VpnState::Activating | VpnState::Connected);
+
+        button(
+            row![
+                svg(svg::Handle::from_path(&lock_icon)).width(25).height(25),
+                column![
+                    text(&vpn.name).font(styling::font::SF_PRO_BOLD),
+                    text(status).size(12),
+                ]
+                .width(Length::Fill),
+            ]
+            .spacing(15)
+            .align_items(Alignment::Center),
+        )
+        .style(if vpn.state == VpnState::Connected {
+            styling::style::Button::Selected
+        } else {
+            styling::style::Button::Default
+        })
+        .width(Length::Fill)
+        .on_press_maybe(
+            (vpn.state != VpnState::Deactivating)
+                .then(|| Message::SetVpnActive(vpn.clone(), !is_up)),
+        )
+        .into()
+    }))
+    .spacing(5);
+
+    match error {
+        Some(error) => column![toggles, text(error).size(12)].spacing(5).into(),
+        None => toggles.into(),
+    }
+}
@@ -0,0 +1,191 @@
+use super::{icon_path, Message, Page};
+use crate::binding::networkmanager::{HotspotConfig, HotspotInfo, Security, WifiNetwork};
+use crate::styling;
+
+use iced::{
+    widget::{button, column, container, row, scrollable, svg, text, text_input},
+    Alignment, Element, Length,
+};
+
+pub fn strength_icon(strength: u8) -> &'static str {
+    match strength {
+        0..=33 => "wifimin",
+        34..=66 => "wifihalf",
+        _ => "wififull-alt",
+    }
+}
+
+/// `prompt` is the network we're asking a passphrase for, if any.
+pub fn view<'a>(
+    wireless_enabled: bool,
+    wifi: Option<&'a WifiNetwork>,
+    networks: &'a [WifiNetwork],
+    prompt: Option<&'a WifiNetwork>,
+    passphrase: &'a str,
+    error: Option<&'a str>,
+) -> Element<'a, Message, styling::theme::Theme> {
+    let icon = |icon_path: String| svg(svg::Handle::from_path(icon_path)).width(25).height(25);
+
+    let header = row![
+        button(icon(icon_path("chevron-left")))
+            .style(styling::style::Button::Icon)
+            .padding(0)
+            .on_press(Message::ShowPage(Page::Main)),
+        text("Wi-Fi")
+            .font(styling::font::SF_PRO_BOLD)
+            .size(16)
+            .width(Length::Fill),
+        button(text(if wireless_enabled { "On" } else { "Off" }))
+            .style(if wireless_enabled {
+                styling::style::Button::Selected
+            } else {
+                styling::style::Button::Default
+            })
+            .on_press(Message::ToggleWifi),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    if !wireless_enabled {
+        return container(column![header, text("Wi-Fi is off")].spacing(20))
+            .width(Length::Fill)
+            .into();
+    }
+
+    let error: Element<Message, styling::theme::Theme> = match error {
+        Some(error) => text(format!("Couldn't connect: {error}")).size(12).into(),
+        None => column![].into(),
+    };
+
+    let networks: Element<Message, styling::theme::Theme> = if networks.is_empty() {
+        text("Scanning…").into()
+    } else {
+        scrollable(
+            column(networks.iter().map(|network| {
+                let is_active = wifi.is_some_and(|wifi| wifi.ssid == network.ssid);
+                let is_prompted = prompt.is_some_and(|prompt| prompt.ssid == network.ssid);
+
+                // There's no way to enter 802.1X credentials here.
+                let can_connect = !is_active
+                    && (network.connection.is_some() || network.security != Security::Enterprise);
+
+                let mut badges = Vec::new();
+                if network.security != Security::Open {
+                    badges.push(network.security.to_string());
+                }
+                if network.connection.is_some() {
+                    badges.push("Saved".to_string());
+                }
+
+                let entry = button(
+                    row![
+                        icon(icon_path(strength_icon(network.strength))),
+                        text(&network.ssid).width(Length::Fill),
+                        text(badges.join(" · ")).size(12),
+                    ]
+                    .spacing(10)
+                    .align_items(Alignment::Center),
+                )
+                .style(if is_active {
+                    styling::style::Button::Selected
+                } else {
+                    styling::style::Button::Default
+                })
+                .width(Length::Fill)
+                .on_press_maybe(can_connect.then(|| Message::SelectWifiNetwork(network.clone())));
+
+                if !is_prompted {
+                    return entry.into();
+                }
+
+                column![
+                    entry,
+                    text_input("Passphrase", passphrase)
+                        .secure(true)
+                        .on_input(Message::SetWifiPassphrase)
+                        .on_submit(Message::ConnectWifi)
+                        .padding(10),
+                    row![
+                        button(text("Cancel")).on_press(Message::CancelWifiPassphrase),
+                        button(text("Connect"))
+                            .style(styling::style::Button::Selected)
+                            .on_press_maybe(
+                                (!passphrase.is_empty()).then_some(Message::ConnectWifi)
+                            ),
+                    ]
+                    .spacing(10),
+                ]
+                .spacing(10)
+                .into()
+            }))
+            .spacing(5),
+        )
+        .height(Length::Fill)
+        .into()
+    };
+
+    container(column![header, error, networks].spacing(20))
+        .width(Length::Fill)
+        .into()
+}
+
+/// Sharing our connection. The name and password can only be changed while it's off.
+pub fn hotspot<'a>(
+    hotspot: Option<&'a HotspotInfo>,
+    config: &'a HotspotConfig,
+    clients: Option<usize>,
+    error: Option<&'a str>,
+) -> Element<'a, Message, styling::theme::Theme> {
+    let header = row![
+        text("Hotspot")
+            .font(styling::font::SF_PRO_BOLD)
+            .width(Length::Fill),
+        button(text(if hotspot.is_some() { "On" } else { "Off" }))
+            .style(if hotspot.is_some() {
+                styling::style::Button::Selected
+            } else {
+                styling::style::Button::Default
+            })
+            .on_press_maybe(
+                (hotspot.is_some() || config.is_valid()).then_some(Message::ToggleHotspot)
+            ),
+    ]
+    .spacing(10)
+    .align_items(Alignment::Center);
+
+    let body: Element<Message, styling::theme::Theme> = match hotspot {
+        Some(hotspot) => {
+            let clients = match clients {
+                Some(1) => "1 device connected".to_string(),
+                Some(clients) => format!("{clients} devices connected"),
+                None => String::new(),
+            };
+
+            column![
+                text(format!("Sharing as {}", hotspot.ssid)),
+                text(clients).size(12),
+            ]
+            .spacing(5)
+            .into()
+        }
+        None => column![
+            text_input("Network name", &config.ssid)
+                .on_input(Message::SetHotspotSsid)
+                .padding(10),
+            text_input("Password, or empty to keep it", &config.password)
+                .secure(true)
+                .on_input(Message::SetHotspotPassword)
+                .on_submit(Message::ToggleHotspot)
+                .padding(10),
+        ]
+        .spacing(10)
+        .into(),
+    };
+
+    let error: Element<Message, styling::theme::Theme> = match error {
+        Some(error) => text(error).size(12).into(),
+        None => column![].into(),
+    };
+
+    column![header, body, error].spacing(10).into()
+}
@@ -2,7 +2,7 @@ use super::theme::Theme;
 
 use iced::{
     application, border,
-    widget::{button, container, slider, svg, text},
+    widget::{button, container, progress_bar, scrollable, slider, svg, text, text_input},
     Background, Border, Color,
 };
 
@@ -30,6 +30,8 @@ pub enum Container {
     #[allow(unused)]
     Rounded,
     HeavyRounded,
+    /// Solid, for drawing bars out of plain containers.
+    Bar,
 }
 
 impl container::StyleSheet for Theme {
@@ -62,6 +64,14 @@ impl container::StyleSheet for Theme {
                 },
                 ..Default::default()
             },
+            Container::Bar => container::Appearance {
+                background: Some(Background::Color(self.palette().accent)),
+                border: Border {
+                    radius: iced::border::Radius::from(2),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
         }
     }
 }
@@ -109,6 +119,8 @@ pub enum Button {
     #[default]
     Default,
     Circular,
+    Selected,
+    Icon,
 }
 
 impl button::StyleSheet for Theme {
@@ -136,10 +148,55 @@ impl button::StyleSheet for Theme {
                 },
                 ..Default::default()
             },
+            Button::Icon => button::Appearance {
+                background: None,
+                text_color: self.palette().text,
+                ..Default::default()
+            },
+            Button::Selected => button::Appearance {
+                background: Some(iced::Background::Color(self.palette().secondary)),
+                text_color: self.palette().background,
+                border: Border {
+                    color: self.palette().secondary,
+                    width: Theme::BORDER_WIDTH,
+                    radius: iced::border::Radius::from(10),
+                },
+                ..Default::default()
+            },
         }
     }
 }
 
+impl scrollable::StyleSheet for Theme {
+    type Style = ();
+
+    fn active(&self, _: &Self::Style) -> scrollable::Appearance {
+        scrollable::Appearance {
+            container: container::Appearance::default(),
+            scrollbar: scrollable::Scrollbar {
+                background: None,
+                border: Border::default(),
+                scroller: scrollable::Scroller {
+                    color: self.palette().secondary,
+                    border: Border {
+                        radius: border::Radius::from(40),
+                        ..Default::default()
+                    },
+                },
+            },
+            gap: None,
+        }
+    }
+
+    fn hovered(
+        &self,
+        style: &Self::Style,
+        _is_mouse_over_scrollbar: bool,
+    ) -> scrollable::Appearance {
+        self.active(style)
+    }
+}
+
 impl slider::StyleSheet for Theme {
     type Style = ();
 
@@ -191,3 +248,64 @@ impl slider::StyleSheet for Theme {
         }
     }
 }
+
+impl text_input::StyleSheet for Theme {
+    type Style = ();
+
+    fn active(&self, _: &Self::Style) -> text_input::Appearance {
+        text_input::Appearance {
+            background: Background::Color(self.palette().background),
+            border: Border {
+                color: self.palette().secondary,
+                width: Theme::BORDER_WIDTH,
+                radius: border::Radius::from(10),
+            },
+            icon_color: self.palette().text,
+        }
+    }
+
+    fn focused(&self, style: &Self::Style) -> text_input::Appearance {
+        self.active(style)
+    }
+
+    fn placeholder_color(&self, _: &Self::Style) -> Color {
+        Color {
+            a: 0.5,
+            ..self.palette().text
+        }
+    }
+
+    fn value_color(&self, _: &Self::Style) -> Color {
+        self.palette().text
+    }
+
+    fn disabled_color(&self, style: &Self::Style) -> Color {
+        self.placeholder_color(style)
+    }
+
+    fn selection_color(&self, _: &Self::Style) -> Color {
+        Color {
+            a: 0.3,
+            ..self.palette().accent
+        }
+    }
+
+    fn disabled(&self, style: &Self::Style) -> text_input::Appearance {
+        self.active(style)
+    }
+}
+
+impl progress_bar::StyleSheet for Theme {
+    type Style = ();
+
+    fn appearance(&self, _: &Self::Style) -> progress_bar::Appearance {
+        progress_bar::Appearance {
+            background: Background::Color(Color {
+                a: 0.2,
+                ..self.palette().secondary
+            }),
+            bar: Background::Color(self.palette().accent),
+            border_radius: border::Radius::from(40),
+        }
+    }
+}